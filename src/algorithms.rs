@@ -0,0 +1,155 @@
+//!
+//! Lookup of algorithm implementations by name, for applications which select algorithms from configuration at runtime.
+//!
+//! Names are matched case-insensitively. Where an algorithm has a standard object identifier, its dotted-decimal form is accepted as an alias.
+
+use alloc::boxed::Box;
+use core::convert::TryInto;
+
+use crate::{
+    digest::{digest_by_name, DynDigest},
+    error::{Error, ErrorKind, Result},
+    symm::{
+        aead::DynAead,
+        chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305},
+    },
+};
+
+const DIGEST_OIDS: [(&str, &str); 7] = [
+    ("1.3.14.3.2.26", "sha1"),
+    ("2.16.840.1.101.3.4.2.1", "sha256"),
+    ("2.16.840.1.101.3.4.2.2", "sha384"),
+    ("2.16.840.1.101.3.4.2.3", "sha512"),
+    ("2.16.840.1.101.3.4.2.4", "sha224"),
+    ("2.16.840.1.101.3.4.2.5", "sha512-224"),
+    ("2.16.840.1.101.3.4.2.6", "sha512-256"),
+];
+
+const AEAD_OIDS: [(&str, &str); 1] = [
+    // id-alg-AEADChaCha20Poly1305, RFC 8103
+    ("1.2.840.113549.1.9.16.3.18", "chacha20-poly1305"),
+];
+
+fn resolve<'a>(name: &'a str, oids: &[(&str, &'a str)]) -> &'a str {
+    oids.iter()
+        .find(|(oid, _)| *oid == name)
+        .map_or(name, |(_, canonical)| canonical)
+}
+
+fn not_found() -> Error {
+    Error::new_with_message(ErrorKind::ProviderNotFound, "unknown algorithm name")
+}
+
+///
+/// Constructs the digest named `name`.
+///
+/// This accepts the names supported by [`digest_by_name`], as well as the object identifiers of those digests.
+/// Returns an error of kind [`ErrorKind::ProviderNotFound`] for any other name.
+///
+/// ## Examples
+///
+/// ```
+/// let sha = lc_crypto::algorithms::digest("2.16.840.1.101.3.4.2.1").unwrap();
+/// assert_eq!(sha.dyn_output_size(), 32);
+/// ```
+pub fn digest(name: &str) -> Result<Box<dyn DynDigest>> {
+    digest_by_name(resolve(name, &DIGEST_OIDS)).ok_or_else(not_found)
+}
+
+///
+/// Constructs the AEAD named `name`, keyed with `key`.
+///
+/// The supported names are `chacha20-poly1305` (or its object identifier) and `xchacha20-poly1305`.
+/// Returns an error of kind [`ErrorKind::ProviderNotFound`] for any other name, or [`ErrorKind::InvalidInput`] if `key` has the wrong length for the algorithm.
+pub fn aead(name: &str, key: &[u8]) -> Result<Box<dyn DynAead>> {
+    let name = resolve(name, &AEAD_OIDS).to_ascii_lowercase();
+    let key_32 = || -> Result<&[u8; 32]> {
+        key.try_into()
+            .map_err(|_| Error::new_with_message(ErrorKind::InvalidInput, "wrong key length"))
+    };
+    match &*name {
+        "chacha20-poly1305" => Ok(Box::new(ChaCha20Poly1305::new(key_32()?))),
+        "xchacha20-poly1305" => Ok(Box::new(XChaCha20Poly1305::new(key_32()?))),
+        _ => Err(not_found()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec;
+
+    use crate::{
+        error::ErrorKind,
+        symm::{
+            aead::{Aead, DynAead},
+            chacha20poly1305::ChaCha20Poly1305,
+        },
+    };
+
+    #[test]
+    pub fn algorithms_digest_names_and_oids() {
+        for (name, size) in [
+            ("sha256", 32),
+            ("SHA-384", 48),
+            ("2.16.840.1.101.3.4.2.1", 32),
+            ("2.16.840.1.101.3.4.2.6", 32),
+            ("1.3.14.3.2.26", 20),
+        ] {
+            let mut digest = super::digest(name).unwrap();
+            assert_eq!(digest.dyn_output_size(), size, "{}", name);
+            let mut out = vec![0u8; size];
+            crate::digest::digest_dyn(&mut *digest, b"abc", &mut out);
+        }
+        let mut by_name = vec![0u8; 32];
+        let mut by_oid = vec![0u8; 32];
+        crate::digest::digest_dyn(&mut *super::digest("sha256").unwrap(), b"abc", &mut by_name);
+        crate::digest::digest_dyn(
+            &mut *super::digest("2.16.840.1.101.3.4.2.1").unwrap(),
+            b"abc",
+            &mut by_oid,
+        );
+        assert_eq!(by_name, by_oid);
+    }
+
+    #[test]
+    pub fn algorithms_unknown_names() {
+        for name in ["md5", "sha3-256", "2.16.840.1.101.3.4.2.99", ""] {
+            assert_eq!(
+                super::digest(name).err().unwrap().kind(),
+                ErrorKind::ProviderNotFound
+            );
+        }
+        assert_eq!(
+            super::aead("aes-256-gcm", &[0; 32]).err().unwrap().kind(),
+            ErrorKind::ProviderNotFound
+        );
+    }
+
+    #[test]
+    pub fn algorithms_aead() {
+        let key = [7u8; 32];
+        for name in [
+            "chacha20-poly1305",
+            "1.2.840.113549.1.9.16.3.18",
+            "XChaCha20-Poly1305",
+        ] {
+            let mut aead = super::aead(name, &key).unwrap();
+            let nonce = vec![1u8; aead.dyn_nonce_size()];
+            let ct = aead.dyn_seal(&nonce, b"aad", b"message");
+            assert_eq!(ct.len(), 7 + aead.dyn_tag_size());
+            assert_eq!(aead.dyn_open(&nonce, b"aad", &ct).unwrap(), b"message");
+        }
+        // With both traits in scope, calls on a concrete AEAD are not ambiguous
+        let mut aead = ChaCha20Poly1305::new(&key);
+        let ct = aead.seal(&[1; 12], b"aad", b"message");
+        assert_eq!(ct, aead.dyn_seal(&[1; 12], b"aad", b"message"));
+        assert_eq!(aead.open(&[1; 12], b"aad", &ct).unwrap(), b"message");
+        assert_eq!(
+            super::aead("chacha20-poly1305", &[0; 16])
+                .err()
+                .unwrap()
+                .kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//!
+//! Low-level primitives whose timing must not depend on secret inputs.
+//!
+//! Where the target allows it, the data-dependent selection is done in inline assembly so that the optimizer cannot turn it back into a branch.
+
+use bytemuck::Pod;
+
+use crate::error::{Error, ErrorKind, Result};
+
+///
+/// Returns `!0` if `a==b` and `0` otherwise, without branching on either value
+#[cfg(target_arch = "x86_64")]
+#[allow(unsafe_code)]
+pub(crate) fn ct_eq_mask(a: usize, b: usize) -> usize {
+    let mut mask = 0usize;
+    // SAFETY:
+    // Only register operands are used, and no memory is accessed
+    unsafe {
+        core::arch::asm!(
+            "cmp {a}, {b}",
+            "cmove {mask}, {ones}",
+            a = in(reg) a,
+            b = in(reg) b,
+            ones = in(reg) !0usize,
+            mask = inout(reg) mask,
+            options(pure, nomem, nostack),
+        );
+    }
+    mask
+}
+
+///
+/// Returns `!0` if `a==b` and `0` otherwise, without branching on either value
+#[cfg(not(target_arch = "x86_64"))]
+pub(crate) fn ct_eq_mask(a: usize, b: usize) -> usize {
+    let x = a ^ b;
+    // The top bit of x|-x is set iff x!=0
+    let ne = (x | x.wrapping_neg()) >> (usize::BITS - 1);
+    ne.wrapping_sub(1)
+}
+
+///
+/// Returns `!0` if `a<b` and `0` otherwise, without branching on either value
+#[cfg(target_arch = "x86_64")]
+#[allow(unsafe_code)]
+pub(crate) fn ct_lt_mask(a: usize, b: usize) -> usize {
+    let mut mask = 0usize;
+    // SAFETY:
+    // Only register operands are used, and no memory is accessed
+    unsafe {
+        core::arch::asm!(
+            "cmp {a}, {b}",
+            "cmovb {mask}, {ones}",
+            a = in(reg) a,
+            b = in(reg) b,
+            ones = in(reg) !0usize,
+            mask = inout(reg) mask,
+            options(pure, nomem, nostack),
+        );
+    }
+    mask
+}
+
+///
+/// Returns `!0` if `a<b` and `0` otherwise, without branching on either value
+#[cfg(not(target_arch = "x86_64"))]
+pub(crate) fn ct_lt_mask(a: usize, b: usize) -> usize {
+    // The top bit is the borrow out of a-b
+    let lt = (a ^ ((a ^ b) | (a.wrapping_sub(b) ^ b))) >> (usize::BITS - 1);
+    lt.wrapping_neg()
+}
+
+///
+/// Looks up `table[index]` in constant time.
+///
+/// Every entry of `table` is read, and the result is selected by masking, so neither the memory access pattern nor the timing depends on `index`.
+/// This makes it suitable for S-Boxes and other tables indexed by secret values.
+///
+/// If `index>=N`, the result is all-zero bytes.
+///
+/// ## Examples
+///
+/// ```
+/// let table = [3u32, 1, 4, 1, 5, 9, 2, 6];
+/// assert_eq!(lc_crypto::asm::ct_table_lookup(5, &table), 9);
+/// ```
+pub fn ct_table_lookup<T: Pod, const N: usize>(index: usize, table: &[T; N]) -> T {
+    let mut ret = T::zeroed();
+    let out = bytemuck::bytes_of_mut(&mut ret);
+    for (i, entry) in table.iter().enumerate() {
+        let mask = ct_eq_mask(i, index) as u8;
+        for (a, &b) in out.iter_mut().zip(bytemuck::bytes_of(entry)) {
+            *a |= b & mask;
+        }
+    }
+    ret
+}
+
+///
+/// Checks that the primitives in this module, and [`crate::cmp::eq`], give correct results on this target.
+///
+/// The inline assembly is target-specific, and unit tests may not have been run on the target a program is deployed to,
+///  so this can be called at startup to catch a miscompilation. Returns an error of kind [`ErrorKind::Other`] if any check fails.
+pub fn self_test() -> Result<()> {
+    use core::hint::black_box;
+    let values = [
+        0,
+        1,
+        2,
+        0x7f,
+        0x80,
+        0xff,
+        usize::MAX >> 1,
+        usize::MAX - 1,
+        usize::MAX,
+    ];
+    let mut ok = true;
+    for &a in &values {
+        for &b in &values {
+            let (a, b) = (black_box(a), black_box(b));
+            ok &= ct_eq_mask(a, b) == if a == b { !0 } else { 0 };
+            ok &= ct_lt_mask(a, b) == if a < b { !0 } else { 0 };
+        }
+    }
+
+    let mut table = [0u16; 64];
+    for (i, v) in table.iter_mut().enumerate() {
+        *v = (i as u16).wrapping_mul(0x9e37) ^ 0x5a5a;
+    }
+    for i in 0..=64 {
+        let expected = table.get(i).copied().unwrap_or(0);
+        ok &= ct_table_lookup(black_box(i), &table) == expected;
+    }
+
+    let x = black_box([0x55u8; 33]);
+    let mut y = x;
+    ok &= crate::cmp::eq(&x, &y);
+    for i in [0, 16, 32] {
+        y[i] ^= 1;
+        ok &= !crate::cmp::eq(&x, black_box(&y));
+        y[i] ^= 1;
+    }
+
+    if ok {
+        Ok(())
+    } else {
+        Err(Error::new_with_message(
+            ErrorKind::Other,
+            "constant-time primitive self-test failed",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    pub fn test_lookup_u32_16() {
+        let mut table = [0u32; 16];
+        for (i, v) in table.iter_mut().enumerate() {
+            *v = (i as u32).wrapping_mul(0x9e3779b9) ^ 0xdeadbeef;
+        }
+        for i in 0..16 {
+            assert_eq!(super::ct_table_lookup(i, &table), table[i]);
+        }
+    }
+
+    #[test]
+    pub fn test_lookup_u64_256() {
+        let mut table = [0u64; 256];
+        for (i, v) in table.iter_mut().enumerate() {
+            *v = (i as u64).wrapping_mul(0x9e3779b97f4a7c15).rotate_left(17);
+        }
+        for i in 0..256 {
+            assert_eq!(super::ct_table_lookup(i, &table), table[i]);
+        }
+    }
+
+    #[test]
+    pub fn test_masks() {
+        for a in [0, 1, 2, 255, 256, usize::MAX - 1, usize::MAX] {
+            for b in [0, 1, 2, 255, 256, usize::MAX - 1, usize::MAX] {
+                assert_eq!(super::ct_eq_mask(a, b), if a == b { !0 } else { 0 });
+                assert_eq!(super::ct_lt_mask(a, b), if a < b { !0 } else { 0 });
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_lookup_out_of_range() {
+        let table = [0xffu8; 4];
+        assert_eq!(super::ct_table_lookup(4, &table), 0);
+        assert_eq!(super::ct_table_lookup(usize::MAX, &table), 0);
+    }
+
+    #[test]
+    pub fn test_self_test() {
+        super::self_test().unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+//!
+//! A C API for the digests, enabled by the `capi` feature.
+//!
+//! A context is a fixed-size struct whose contents are private, so C code sees it as an opaque type.
+//! Its size and alignment are returned by [`lc_sha256_ctx_size`] and [`lc_sha256_ctx_align`], and the caller allocates it and only accesses it through these functions:
+//!
+//! ```c
+//! typedef struct lc_sha256_ctx lc_sha256_ctx;
+//! size_t lc_sha256_ctx_size(void);
+//! size_t lc_sha256_ctx_align(void);
+//! void lc_sha256_init(lc_sha256_ctx *ctx);
+//! void lc_sha256_update(lc_sha256_ctx *ctx, const unsigned char *data, size_t len);
+//! void lc_sha256_final(lc_sha256_ctx *ctx, unsigned char *out);
+//! void lc_sha256(const unsigned char *data, size_t len, unsigned char *out);
+//!
+//! lc_sha256_ctx *ctx = aligned_alloc(lc_sha256_ctx_align(), lc_sha256_ctx_size());
+//! lc_sha256_init(ctx);
+//! lc_sha256_update(ctx, data, len);
+//! lc_sha256_final(ctx, out);
+//! free(ctx);
+//! ```
+#![allow(unsafe_code)]
+
+use zeroize::Zeroize;
+
+use crate::digest::{sha2::Sha256, Digest};
+
+///
+/// The context for an incremental SHA-256 computation through the C API
+#[repr(C)]
+pub struct LcSha256Ctx {
+    digest: Sha256,
+    buf: [u8; 64],
+    buf_len: usize,
+}
+
+pub const LC_SHA256_CTX_SIZE: usize = core::mem::size_of::<LcSha256Ctx>();
+pub const LC_SHA256_CTX_ALIGN: usize = core::mem::align_of::<LcSha256Ctx>();
+
+///
+/// Returns the size in bytes of a SHA-256 context, [`LC_SHA256_CTX_SIZE`]. This is always a multiple of [`lc_sha256_ctx_align`].
+#[no_mangle]
+pub extern "C" fn lc_sha256_ctx_size() -> usize {
+    LC_SHA256_CTX_SIZE
+}
+
+///
+/// Returns the required alignment in bytes of a SHA-256 context, [`LC_SHA256_CTX_ALIGN`]
+#[no_mangle]
+pub extern "C" fn lc_sha256_ctx_align() -> usize {
+    LC_SHA256_CTX_ALIGN
+}
+
+impl LcSha256Ctx {
+    const fn new() -> Self {
+        Self {
+            digest: Sha256::new(),
+            buf: [0; 64],
+            buf_len: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        // A full buffer is only hashed once more data arrives, since the last block must go to do_final
+        while !data.is_empty() {
+            if self.buf_len == 64 {
+                self.digest.update(&self.buf);
+                self.buf_len = 0;
+            }
+            let n = (64 - self.buf_len).min(data.len());
+            self.buf[self.buf_len..][..n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+        }
+    }
+
+    fn finish(&mut self, out: &mut [u8; 32]) {
+        self.digest.do_final(&self.buf[..self.buf_len], out);
+        self.buf.zeroize();
+        *self = Self::new();
+    }
+}
+
+///
+/// Builds a slice from a C pointer and length, allowing a null pointer when `len` is 0
+///
+/// # Safety
+/// If `len` is nonzero, `data` must be valid for reads of `len` bytes
+unsafe fn slice_from_c<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        // SAFETY:
+        // Guaranteed by the caller
+        unsafe { core::slice::from_raw_parts(data, len) }
+    }
+}
+
+///
+/// Initializes (or resets) the SHA-256 context at `ctx`.
+///
+/// # Safety
+/// `ctx` must be valid for writes of [`lc_sha256_ctx_size`] bytes and aligned to [`lc_sha256_ctx_align`]. It need not be initialized.
+#[no_mangle]
+pub unsafe extern "C" fn lc_sha256_init(ctx: *mut LcSha256Ctx) {
+    // SAFETY:
+    // Guaranteed by the caller. The write does not read or drop the old contents
+    unsafe { ctx.write(LcSha256Ctx::new()) }
+}
+
+///
+/// Adds `len` bytes at `data` to the SHA-256 computation in `ctx`.
+///
+/// # Safety
+/// `ctx` must have been initialized by [`lc_sha256_init`], and if `len` is nonzero, `data` must be valid for reads of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn lc_sha256_update(ctx: *mut LcSha256Ctx, data: *const u8, len: usize) {
+    // SAFETY:
+    // Guaranteed by the caller
+    unsafe { (*ctx).update(slice_from_c(data, len)) }
+}
+
+///
+/// Writes the 32-byte SHA-256 hash of the data given to `ctx` to `out`, and resets `ctx` as by [`lc_sha256_init`].
+///
+/// # Safety
+/// `ctx` must have been initialized by [`lc_sha256_init`], and `out` must be valid for writes of 32 bytes
+#[no_mangle]
+pub unsafe extern "C" fn lc_sha256_final(ctx: *mut LcSha256Ctx, out: *mut u8) {
+    // SAFETY:
+    // Guaranteed by the caller. [u8; 32] has alignment 1
+    unsafe { (*ctx).finish(&mut *(out as *mut [u8; 32])) }
+}
+
+///
+/// Writes the 32-byte SHA-256 hash of the `len` bytes at `data` to `out`.
+///
+/// # Safety
+/// If `len` is nonzero, `data` must be valid for reads of `len` bytes, and `out` must be valid for writes of 32 bytes
+#[no_mangle]
+pub unsafe extern "C" fn lc_sha256(data: *const u8, len: usize, out: *mut u8) {
+    let mut ctx = LcSha256Ctx::new();
+    // SAFETY:
+    // Guaranteed by the caller
+    unsafe {
+        ctx.update(slice_from_c(data, len));
+        ctx.finish(&mut *(out as *mut [u8; 32]));
+    }
+}
+
+#[cfg(all(test, feature = "capi"))]
+mod test {
+    use core::mem::MaybeUninit;
+
+    use alloc::vec::Vec;
+
+    use super::{
+        lc_sha256, lc_sha256_ctx_align, lc_sha256_ctx_size, lc_sha256_final, lc_sha256_init,
+        lc_sha256_update, LcSha256Ctx,
+    };
+    use crate::digest::{digest, sha2::Sha256};
+
+    #[test]
+    pub fn capi_sha256_matches_digest() {
+        let data: Vec<u8> = (0..300u32).map(|i| (i * 13) as u8).collect();
+        for len in [0, 1, 55, 56, 63, 64, 65, 128, 300] {
+            let mut expected = [0u8; 32];
+            digest(Sha256::new(), &data[..len], &mut expected);
+
+            let mut one_shot = [0u8; 32];
+            let mut chunked = [0u8; 32];
+            let mut ctx = MaybeUninit::<LcSha256Ctx>::uninit();
+            // SAFETY:
+            // All pointers refer to live local buffers of the required sizes
+            #[allow(unsafe_code)]
+            unsafe {
+                lc_sha256(data.as_ptr(), len, one_shot.as_mut_ptr());
+                lc_sha256_init(ctx.as_mut_ptr());
+                for chunk in data[..len].chunks(7) {
+                    lc_sha256_update(ctx.as_mut_ptr(), chunk.as_ptr(), chunk.len());
+                }
+                lc_sha256_update(ctx.as_mut_ptr(), core::ptr::null(), 0);
+                lc_sha256_final(ctx.as_mut_ptr(), chunked.as_mut_ptr());
+            }
+            assert_eq!(one_shot, expected, "length {}", len);
+            assert_eq!(chunked, expected, "length {}", len);
+        }
+    }
+
+    #[test]
+    pub fn capi_sha256_ctx_layout() {
+        assert_eq!(lc_sha256_ctx_size(), core::mem::size_of::<LcSha256Ctx>());
+        assert_eq!(lc_sha256_ctx_align(), core::mem::align_of::<LcSha256Ctx>());
+        assert_eq!(lc_sha256_ctx_size() % lc_sha256_ctx_align(), 0);
+    }
+}
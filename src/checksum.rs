@@ -0,0 +1,373 @@
+//!
+//! Non-cryptographic checksums, for detecting accidental corruption in protocol framing and file formats.
+//!
+//! None of these offer any protection against deliberate modification, since anyone can recompute them.
+//! Use a MAC (such as [`crate::digest::Hmac`]) or an AEAD where an attacker may control the data.
+//! They are also not constant-time: table lookups are indexed by the data.
+
+///
+/// A checksum computed incrementally over a stream of bytes
+pub trait Checksum: Default {
+    type Output;
+
+    ///
+    /// Adds `bytes` to the checksum. Input may be split across calls at any point
+    fn update(&mut self, bytes: &[u8]);
+
+    ///
+    /// Returns the checksum of everything passed to [`Checksum::update`] so far
+    fn finalize(&self) -> Self::Output;
+
+    ///
+    /// Computes the checksum of `bytes` in one call
+    fn checksum(bytes: &[u8]) -> Self::Output {
+        let mut c = Self::default();
+        c.update(bytes);
+        c.finalize()
+    }
+}
+
+const fn crc32_table(poly: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { (c >> 1) ^ poly } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table(0xedb88320);
+const CRC32C_TABLE: [u32; 256] = crc32_table(0x82f63b78);
+
+fn crc32_update_table(table: &[u32; 256], mut crc: u32, bytes: &[u8]) -> u32 {
+    for &b in bytes {
+        crc = table[((crc ^ u32::from(b)) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+///
+/// The CRC-32 of ISO-HDLC, as used by zlib, PNG, and Ethernet (reflected polynomial `0xedb88320`)
+#[derive(Clone)]
+pub struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    pub const fn new() -> Self {
+        Self { crc: !0 }
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checksum for Crc32 {
+    type Output = u32;
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.crc = crc32_update_table(&CRC32_TABLE, self.crc, bytes);
+    }
+
+    fn finalize(&self) -> u32 {
+        !self.crc
+    }
+}
+
+///
+/// The CRC-32C (Castagnoli) checksum, as used by iSCSI, SCTP, and ext4 (reflected polynomial `0x82f63b78`).
+///
+/// On x86_64 processors with SSE4.2, this uses the `crc32` instruction.
+#[derive(Clone)]
+pub struct Crc32c {
+    crc: u32,
+}
+
+impl Crc32c {
+    pub const fn new() -> Self {
+        Self { crc: !0 }
+    }
+}
+
+impl Default for Crc32c {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checksum for Crc32c {
+    type Output = u32;
+
+    fn update(&mut self, bytes: &[u8]) {
+        #[cfg(target_arch = "x86_64")]
+        if sse42::is_available() {
+            self.crc = sse42::crc32c_update(self.crc, bytes);
+            return;
+        }
+        self.crc = crc32_update_table(&CRC32C_TABLE, self.crc, bytes);
+    }
+
+    fn finalize(&self) -> u32 {
+        !self.crc
+    }
+}
+
+const ADLER_MOD: u32 = 65521;
+// The largest n such that 255*n*(n+1)/2 + (n+1)*(ADLER_MOD-1) fits in a u32, so sums only need reducing once per n bytes
+const ADLER_NMAX: usize = 5552;
+
+///
+/// The Adler-32 checksum of RFC 1950, as used by zlib
+#[derive(Clone)]
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    pub const fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checksum for Adler32 {
+    type Output = u32;
+
+    fn update(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(ADLER_NMAX) {
+            for &x in chunk {
+                self.a += u32::from(x);
+                self.b += self.a;
+            }
+            self.a %= ADLER_MOD;
+            self.b %= ADLER_MOD;
+        }
+    }
+
+    fn finalize(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+///
+/// The Fletcher-16 checksum, with both sums of bytes taken modulo 255
+#[derive(Clone, Default)]
+pub struct Fletcher16 {
+    a: u16,
+    b: u16,
+}
+
+impl Fletcher16 {
+    pub const fn new() -> Self {
+        Self { a: 0, b: 0 }
+    }
+}
+
+impl Checksum for Fletcher16 {
+    type Output = u16;
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &x in bytes {
+            self.a = (self.a + u16::from(x)) % 255;
+            self.b = (self.b + self.a) % 255;
+        }
+    }
+
+    fn finalize(&self) -> u16 {
+        (self.b << 8) | self.a
+    }
+}
+
+///
+/// The Fletcher-32 checksum, over little-endian 16-bit words with both sums taken modulo 65535.
+///
+/// An odd number of bytes in total is padded with a zero byte. Input may still be split at odd offsets, since a trailing odd byte is held until the next update.
+#[derive(Clone, Default)]
+pub struct Fletcher32 {
+    a: u32,
+    b: u32,
+    pending: Option<u8>,
+}
+
+impl Fletcher32 {
+    pub const fn new() -> Self {
+        Self {
+            a: 0,
+            b: 0,
+            pending: None,
+        }
+    }
+
+    fn add_word(&mut self, word: u16) {
+        self.a = (self.a + u32::from(word)) % 65535;
+        self.b = (self.b + self.a) % 65535;
+    }
+}
+
+impl Checksum for Fletcher32 {
+    type Output = u32;
+
+    fn update(&mut self, mut bytes: &[u8]) {
+        if let (Some(lo), Some((&hi, rest))) = (self.pending, bytes.split_first()) {
+            self.add_word(u16::from_le_bytes([lo, hi]));
+            self.pending = None;
+            bytes = rest;
+        }
+        let mut words = bytes.chunks_exact(2);
+        for w in &mut words {
+            self.add_word(u16::from_le_bytes([w[0], w[1]]));
+        }
+        if let [x] = words.remainder() {
+            self.pending = Some(*x);
+        }
+    }
+
+    fn finalize(&self) -> u32 {
+        let mut tmp = self.clone();
+        if let Some(lo) = tmp.pending {
+            tmp.add_word(u16::from(lo));
+        }
+        (tmp.b << 16) | tmp.a
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod sse42 {
+    use core::arch::x86_64::*;
+
+    pub fn is_available() -> bool {
+        crate::cpu_feature_detected!("sse4.2")
+    }
+
+    #[allow(unsafe_code)]
+    pub fn crc32c_update(crc: u32, bytes: &[u8]) -> u32 {
+        assert!(is_available());
+        // SAFETY:
+        // We just checked that the processor supports SSE4.2
+        unsafe { crc32c_update_sse42(crc, bytes) }
+    }
+
+    ///
+    /// # Safety
+    /// The processor must support SSE4.2
+    #[allow(unsafe_code)]
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn crc32c_update_sse42(crc: u32, bytes: &[u8]) -> u32 {
+        let mut crc = u64::from(crc);
+        let mut words = bytes.chunks_exact(8);
+        for w in &mut words {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(w);
+            crc = _mm_crc32_u64(crc, u64::from_le_bytes(word));
+        }
+        let mut crc = crc as u32;
+        for &b in words.remainder() {
+            crc = _mm_crc32_u8(crc, b);
+        }
+        crc
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Adler32, Checksum, Crc32, Crc32c, Fletcher16, Fletcher32};
+
+    #[test]
+    pub fn crc32_check_value() {
+        assert_eq!(Crc32::checksum(b"123456789"), 0xcbf43926);
+        assert_eq!(Crc32::checksum(b""), 0);
+    }
+
+    #[test]
+    pub fn crc32c_check_value() {
+        assert_eq!(Crc32c::checksum(b"123456789"), 0xe3069283);
+        assert_eq!(Crc32c::checksum(b""), 0);
+    }
+
+    #[test]
+    pub fn crc32_incremental() {
+        let data: alloc::vec::Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        for split in [0, 1, 7, 8, 9, 500, 1000] {
+            let mut a = Crc32::new();
+            a.update(&data[..split]);
+            a.update(&data[split..]);
+            assert_eq!(a.finalize(), Crc32::checksum(&data));
+            let mut b = Crc32c::new();
+            b.update(&data[..split]);
+            b.update(&data[split..]);
+            assert_eq!(b.finalize(), Crc32c::checksum(&data));
+        }
+    }
+
+    #[test]
+    pub fn adler32_wikipedia() {
+        assert_eq!(Adler32::checksum(b"Wikipedia"), 0x11e60398);
+        assert_eq!(Adler32::checksum(b""), 1);
+    }
+
+    #[test]
+    pub fn adler32_long_input() {
+        // Long enough to need several reductions; the expected value is from zlib
+        let data = alloc::vec![0xffu8; 100000];
+        assert_eq!(Adler32::checksum(&data), 0x149a302c);
+    }
+
+    #[test]
+    pub fn fletcher_check_values() {
+        for (input, f16, f32) in [
+            (&b"abcde"[..], 0xc8f0, 0xf04fc729),
+            (b"abcdef", 0x2057, 0x56502d2a),
+            (b"abcdefgh", 0x0627, 0xebe19591),
+        ] {
+            assert_eq!(Fletcher16::checksum(input), f16);
+            assert_eq!(Fletcher32::checksum(input), f32);
+        }
+    }
+
+    #[test]
+    pub fn fletcher32_odd_splits() {
+        let data = b"abcdefgh";
+        for split in 0..=data.len() {
+            let mut c = Fletcher32::new();
+            c.update(&data[..split]);
+            c.update(&data[split..]);
+            assert_eq!(c.finalize(), 0xebe19591);
+        }
+        let mut c = Fletcher32::new();
+        for b in b"abcde" {
+            c.update(core::slice::from_ref(b));
+        }
+        assert_eq!(c.finalize(), 0xf04fc729);
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    pub fn crc32c_sse42_matches_table() {
+        if !super::sse42::is_available() {
+            return;
+        }
+        let data: alloc::vec::Vec<u8> = (0..300u32).map(|i| (i * 31 + 5) as u8).collect();
+        for len in [0, 1, 7, 8, 9, 63, 64, 300] {
+            assert_eq!(
+                super::sse42::crc32c_update(!0, &data[..len]),
+                super::crc32_update_table(&super::CRC32C_TABLE, !0, &data[..len])
+            );
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::asm::{ct_eq_mask, ct_lt_mask};
+
 ///
 /// Compares two values for equality in constant time based on the input
 ///
@@ -31,8 +33,146 @@ pub fn eq(a: &[u8], b: &[u8]) -> bool {
     ret
 }
 
+///
+/// The first position at which two slices compared by [`debug_eq`] differ
+#[cfg(any(debug_assertions, not(feature = "strict-ct")))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DiffError {
+    index: usize,
+}
+
+#[cfg(any(debug_assertions, not(feature = "strict-ct")))]
+impl DiffError {
+    ///
+    /// The index of the first differing byte. If one slice is a prefix of the other, this is the length of the shorter one.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+#[cfg(any(debug_assertions, not(feature = "strict-ct")))]
+impl core::fmt::Display for DiffError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "values differ at index {}", self.index)
+    }
+}
+
+#[cfg(all(
+    any(test, feature = "std"),
+    any(debug_assertions, not(feature = "strict-ct"))
+))]
+impl std::error::Error for DiffError {}
+
+///
+/// Compares two byte slices, returning the index of the first difference.
+///
+/// **This is NOT constant-time.** It returns as soon as a difference is found, and the index it reports is exactly what a timing attack would recover.
+/// It is for tests and debugging only: never use it on secret data, MACs, or anything an attacker can observe. Use [`eq`] instead.
+///
+/// Unlike [`eq`], slices of different lengths are compared rather than panicking.
+/// This function is unavailable in release builds with the `strict-ct` feature enabled.
+///
+/// ## Examples
+///
+/// ```
+/// let x = [0, 1, 2, 3];
+/// assert!(lc_crypto::cmp::debug_eq(&x, &[0, 1, 2, 3]).is_ok());
+/// assert_eq!(lc_crypto::cmp::debug_eq(&x, &[0, 1, 9, 3]).unwrap_err().index(), 2);
+/// ```
+#[cfg(any(debug_assertions, not(feature = "strict-ct")))]
+pub fn debug_eq(a: &[u8], b: &[u8]) -> Result<(), DiffError> {
+    match a.iter().zip(b).position(|(x, y)| x != y) {
+        Some(index) => Err(DiffError { index }),
+        None if a.len() != b.len() => Err(DiffError {
+            index: a.len().min(b.len()),
+        }),
+        None => Ok(()),
+    }
+}
+
+///
+/// Ordering of fixed-width values in constant time
+pub trait ConstantTimeOrd {
+    ///
+    /// Compares `self` with `other`, returning `(self<other, self==other, self>other)`, without branching on either value.
+    ///
+    /// Exactly one of the three results is true.
+    fn ct_cmp(&self, other: &Self) -> (bool, bool, bool);
+}
+
+///
+/// Compares arrays as big-endian integers, which agrees with the lexicographic [`Ord`] on `[u8; N]`.
+///
+/// ## Examples
+///
+/// ```
+/// use lc_crypto::cmp::ConstantTimeOrd;
+/// assert_eq!([1u8, 2, 3].ct_cmp(&[1, 3, 0]), (true, false, false));
+/// ```
+impl<const N: usize> ConstantTimeOrd for [u8; N] {
+    fn ct_cmp(&self, other: &Self) -> (bool, bool, bool) {
+        let (mut lt, mut gt, mut eq) = (0usize, 0usize, !0usize);
+        for (&a, &b) in self.iter().zip(other) {
+            let (a, b) = (usize::from(a), usize::from(b));
+            // Only the first differing byte decides the order
+            lt |= eq & ct_lt_mask(a, b);
+            gt |= eq & ct_lt_mask(b, a);
+            eq &= ct_eq_mask(a, b);
+        }
+        (lt & 1 != 0, eq & 1 != 0, gt & 1 != 0)
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use super::ConstantTimeOrd;
+
+    fn check<const N: usize>(a: [u8; N], b: [u8; N]) {
+        let expected = match a.cmp(&b) {
+            core::cmp::Ordering::Less => (true, false, false),
+            core::cmp::Ordering::Equal => (false, true, false),
+            core::cmp::Ordering::Greater => (false, false, true),
+        };
+        assert_eq!(a.ct_cmp(&b), expected);
+    }
+
+    #[test]
+    pub fn test_ct_cmp() {
+        check([], []);
+        for a in 0..=255u8 {
+            for b in [0, 1, 127, 128, 254, 255] {
+                check([a], [b]);
+            }
+        }
+        check([1, 0, 0, 0], [0, 255, 255, 255]);
+        check([0, 255, 255, 255], [1, 0, 0, 0]);
+        check([7; 12], [7; 12]);
+        let mut x = [0x5au8; 16];
+        for i in 0..16 {
+            let mut y = x;
+            y[i] ^= 0x80;
+            check(x, y);
+            check(y, x);
+            x[i] = x[i].wrapping_add(1);
+        }
+    }
+
+    #[test]
+    pub fn test_debug_eq() {
+        let x = [0x5au8; 8];
+        assert_eq!(super::debug_eq(&x, &x), Ok(()));
+        assert_eq!(super::debug_eq(&[], &[]), Ok(()));
+        for i in 0..8 {
+            let mut y = x;
+            y[i] ^= 1;
+            // Later differences don't change the reported index
+            y[7] ^= 0x80;
+            assert_eq!(super::debug_eq(&x, &y).unwrap_err().index(), i);
+        }
+        assert_eq!(super::debug_eq(&x, &x[..5]).unwrap_err().index(), 5);
+        assert_eq!(super::debug_eq(&[], &x).unwrap_err().index(), 0);
+    }
+
     #[test]
     pub fn test_eq_eq() {
         let x = [0, 1, 2, 3];
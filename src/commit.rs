@@ -0,0 +1,156 @@
+//!
+//! Hash-based commitments, for commit-reveal protocols.
+//!
+//! A commitment to `value` is `D(value || nonce)` for a fresh 32-byte random nonce. It hides the value as long as the nonce stays secret,
+//!  and binds the committer to it as long as `D` is collision resistant. Opening reveals the value and the nonce.
+
+use alloc::{vec, vec::Vec};
+use zeroize::Zeroizing;
+
+use crate::{digest::Digest, rand::SecureRandom};
+
+///
+/// The size of the random nonce in an [`Opening`], in bytes
+pub const NONCE_SIZE: usize = 32;
+
+///
+/// A commitment produced by [`commit`], which can be published before the value is revealed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Commitment(Vec<u8>);
+
+impl Commitment {
+    ///
+    /// Wraps a commitment received from the committer.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+///
+/// The nonce which opens a [`Commitment`]. It must be kept secret until the value is revealed.
+#[derive(Clone)]
+pub struct Opening(Zeroizing<[u8; NONCE_SIZE]>);
+
+impl Opening {
+    ///
+    /// Wraps an opening received from the committer.
+    pub fn from_bytes(bytes: [u8; NONCE_SIZE]) -> Self {
+        Self(Zeroizing::new(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; NONCE_SIZE] {
+        &self.0
+    }
+}
+
+impl core::fmt::Debug for Opening {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("Opening(<redacted>)")
+    }
+}
+
+fn commitment_hash<D: Digest + Default>(value: &[u8], nonce: &[u8; NONCE_SIZE]) -> Vec<u8> {
+    let mut input = Zeroizing::new(Vec::with_capacity(value.len() + NONCE_SIZE));
+    input.extend_from_slice(value);
+    input.extend_from_slice(nonce);
+    let mut out = vec![0u8; D::OUTPUT_SIZE];
+    crate::digest::digest(D::default(), &input, &mut out);
+    out
+}
+
+///
+/// Commits to `value` with a nonce drawn from `rand`, returning the commitment to publish and the opening to keep until the value is revealed.
+///
+/// ## Examples
+///
+/// ```
+/// use lc_crypto::{commit, digest::sha2::Sha256, rand::{DoubleDigestRandom, SecureRandom}};
+/// let mut rand = DoubleDigestRandom::new(Sha256::default(), Sha256::default());
+/// rand.seed([1, 2, 3, 4]);
+/// let (commitment, opening) = commit::commit::<Sha256, _>(b"heads", &mut rand);
+/// assert!(commit::verify::<Sha256>(&commitment, b"heads", &opening));
+/// assert!(!commit::verify::<Sha256>(&commitment, b"tails", &opening));
+/// ```
+pub fn commit<D: Digest + Default, R: SecureRandom + ?Sized>(
+    value: &[u8],
+    rand: &mut R,
+) -> (Commitment, Opening) {
+    let mut nonce = Zeroizing::new([0u8; NONCE_SIZE]);
+    rand.next_bytes(&mut *nonce);
+    let commitment = Commitment(commitment_hash::<D>(value, &nonce));
+    (commitment, Opening(nonce))
+}
+
+///
+/// Checks that `commitment` opens to `value` with `opening`. The hashes are compared in constant time.
+///
+/// A commitment of the wrong length for `D` is rejected.
+pub fn verify<D: Digest + Default>(
+    commitment: &Commitment,
+    value: &[u8],
+    opening: &Opening,
+) -> bool {
+    let expected = commitment_hash::<D>(value, &opening.0);
+    commitment.0.len() == expected.len() && crate::cmp::eq(&commitment.0, &expected)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{commit, verify, Commitment, Opening, NONCE_SIZE};
+    use crate::{
+        digest::sha2::{Sha256, Sha512},
+        rand::{DoubleDigestRandom, SecureRandom},
+    };
+
+    fn rand() -> DoubleDigestRandom<Sha256, Sha256> {
+        let mut rand = DoubleDigestRandom::new(Sha256::default(), Sha256::default());
+        rand.seed([0x0123_4567_89ab_cdef, 42]);
+        rand
+    }
+
+    #[test]
+    pub fn commit_opens() {
+        let mut rand = rand();
+        for value in [&b""[..], b"x", &[0xa5; 200]] {
+            let (c, o) = commit::<Sha256, _>(value, &mut rand);
+            assert_eq!(c.as_bytes().len(), 32);
+            assert!(verify::<Sha256>(&c, value, &o));
+            // The commitment and opening survive a round trip through bytes
+            let c2 = Commitment::from_bytes(c.as_bytes());
+            let o2 = Opening::from_bytes(*o.as_bytes());
+            assert!(verify::<Sha256>(&c2, value, &o2));
+        }
+        let (c, o) = commit::<Sha512, _>(b"value", &mut rand);
+        assert!(verify::<Sha512>(&c, b"value", &o));
+        assert!(!verify::<Sha256>(&c, b"value", &o));
+    }
+
+    #[test]
+    pub fn commit_wrong_value() {
+        let mut rand = rand();
+        let (c, o) = commit::<Sha256, _>(b"value", &mut rand);
+        assert!(!verify::<Sha256>(&c, b"valuf", &o));
+        assert!(!verify::<Sha256>(&c, b"value\0", &o));
+        assert!(!verify::<Sha256>(&c, b"", &o));
+    }
+
+    #[test]
+    pub fn commit_wrong_nonce() {
+        let mut rand = rand();
+        let (c, o) = commit::<Sha256, _>(b"value", &mut rand);
+        for i in 0..NONCE_SIZE {
+            let mut nonce = *o.as_bytes();
+            nonce[i] ^= 1;
+            assert!(!verify::<Sha256>(&c, b"value", &Opening::from_bytes(nonce)));
+        }
+        // Committing twice to the same value uses fresh nonces
+        let (c2, o2) = commit::<Sha256, _>(b"value", &mut rand);
+        assert_ne!(c, c2);
+        assert!(!verify::<Sha256>(&c2, b"value", &o));
+        assert!(verify::<Sha256>(&c2, b"value", &o2));
+    }
+}
@@ -1,9 +1,16 @@
-use alloc::{boxed::Box, vec};
+use core::hash::Hasher;
+
+use alloc::{boxed::Box, vec, vec::Vec};
 use zeroize::Zeroizing;
 
+use crate::error::{Error, ErrorKind, Result};
+
+pub mod hkdf;
+pub mod multibuffer;
 #[cfg(feature = "sha1")]
 pub mod sha1;
 pub mod sha2;
+pub mod sp800_185;
 
 pub trait Digest {
     const OUTPUT_SIZE: usize;
@@ -11,9 +18,70 @@ pub trait Digest {
 
     fn init(&mut self);
 
+    ///
+    /// Absorbs one block of exactly `BLOCK_SIZE` bytes.
+    ///
+    /// Implementations may panic if `block` has the wrong length. See [`Digest::try_update`] for a checked form.
     fn update(&mut self, block: &[u8]);
 
+    ///
+    /// Absorbs the final block of at most `BLOCK_SIZE` bytes and writes the `OUTPUT_SIZE` byte output to `out`.
+    ///
+    /// Implementations may panic if `lblock` or `out` has the wrong length. See [`Digest::try_do_final`] for a checked form.
     fn do_final(&mut self, lblock: &[u8], out: &mut [u8]);
+
+    ///
+    /// Checks that `block` is exactly `BLOCK_SIZE` bytes, then absorbs it as [`Digest::update`].
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidInput`] instead of panicking if the length is wrong.
+    fn try_update(&mut self, block: &[u8]) -> Result<()> {
+        if block.len() != Self::BLOCK_SIZE {
+            return Err(Error::new_with_message(
+                ErrorKind::InvalidInput,
+                "digest block has the wrong length",
+            ));
+        }
+        self.update(block);
+        Ok(())
+    }
+
+    ///
+    /// Checks that `lblock` is at most `BLOCK_SIZE` bytes and `out` is exactly `OUTPUT_SIZE` bytes, then finishes as [`Digest::do_final`].
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidInput`] instead of panicking if either length is wrong.
+    fn try_do_final(&mut self, lblock: &[u8], out: &mut [u8]) -> Result<()> {
+        if lblock.len() > Self::BLOCK_SIZE || out.len() != Self::OUTPUT_SIZE {
+            return Err(Error::new_with_message(
+                ErrorKind::InvalidInput,
+                "digest final block or output has the wrong length",
+            ));
+        }
+        self.do_final(lblock, out);
+        Ok(())
+    }
+
+    ///
+    /// Returns `Self::BLOCK_SIZE`, for code which has a digest value but not its type
+    fn block_size(&self) -> usize {
+        Self::BLOCK_SIZE
+    }
+
+    ///
+    /// Returns `Self::OUTPUT_SIZE`, for code which has a digest value but not its type
+    fn output_size(&self) -> usize {
+        Self::OUTPUT_SIZE
+    }
+
+    ///
+    /// Finishes the digest as [`Digest::do_final`], but only writes the first `out.len()` bytes of the output (at most `OUTPUT_SIZE`).
+    ///
+    /// This is useful for MACs and AEAD tags which are truncated to a runtime length.
+    fn do_final_truncated(&mut self, lblock: &[u8], out: &mut [u8]) {
+        let mut full = Zeroizing::new(vec![0u8; Self::OUTPUT_SIZE].into_boxed_slice());
+        self.do_final(lblock, &mut full);
+        let len = out.len().min(Self::OUTPUT_SIZE);
+        out[..len].copy_from_slice(&full[..len]);
+    }
 }
 
 impl<D: Digest> Digest for &mut D {
@@ -61,6 +129,287 @@ pub fn digest<D: Digest>(mut digest: D, bytes: &[u8], out: &mut [u8]) {
     digest.do_final(last.unwrap_or(&[]), out)
 }
 
+///
+/// Hashes `bytes` as [`digest`], but returns an error of kind [`ErrorKind::InvalidInput`] instead of panicking if `out` is not exactly `D::OUTPUT_SIZE` bytes
+pub fn try_digest<D: Digest>(digest: D, bytes: &[u8], out: &mut [u8]) -> Result<()> {
+    if out.len() != D::OUTPUT_SIZE {
+        return Err(Error::new_with_message(
+            ErrorKind::InvalidInput,
+            "digest output has the wrong length",
+        ));
+    }
+    self::digest(digest, bytes, out);
+    Ok(())
+}
+
+///
+/// A known answer for a [`Digest`]: the output expected when hashing a fixed input, for use by [`self_test`]
+pub trait KnownAnswer: Digest {
+    const KAT_INPUT: &'static [u8] = b"abc";
+    const KAT_OUTPUT: &'static [u8];
+}
+
+///
+/// Hashes the known-answer input of `D` and checks the result, as a power-on self test.
+///
+/// Returns an error of kind [`ErrorKind::Other`] if the output does not match, which indicates that the implementation is miscompiled or corrupted.
+///
+/// ## Examples
+///
+/// ```
+/// use lc_crypto::digest::{self_test, sha2::Sha256};
+/// self_test::<Sha256>().unwrap();
+/// ```
+pub fn self_test<D: KnownAnswer + Default>() -> Result<()> {
+    let mut out = vec![0u8; D::OUTPUT_SIZE];
+    digest(D::default(), D::KAT_INPUT, &mut out);
+    if out.len() == D::KAT_OUTPUT.len() && crate::cmp::eq(&out, D::KAT_OUTPUT) {
+        Ok(())
+    } else {
+        Err(Error::new_with_message(
+            ErrorKind::Other,
+            "digest known-answer test failed",
+        ))
+    }
+}
+
+///
+/// The MGF1 mask generation function of RFC 8017 appendix B.2.1, filling `out` with a mask derived from `seed`.
+///
+/// The mask is the concatenation of `D(seed || counter)` for a 4-byte big-endian counter starting at 0, truncated to `out.len()`.
+/// This is used by RSA-OAEP and RSA-PSS, but is also a simple extendable-output function in its own right.
+///
+/// Returns an error of kind [`ErrorKind::InvalidInput`] if `out` is longer than `2^32` digest outputs.
+pub fn mgf1<D: Digest + Default>(seed: &[u8], out: &mut [u8]) -> Result<()> {
+    if out.len().div_ceil(D::OUTPUT_SIZE) as u64 > 1 << 32 {
+        return Err(Error::new_with_message(
+            ErrorKind::InvalidInput,
+            "mask too long for MGF1",
+        ));
+    }
+    let mut input = Zeroizing::new(Vec::with_capacity(seed.len() + 4));
+    input.extend_from_slice(seed);
+    input.extend_from_slice(&[0; 4]);
+    let mut block = Zeroizing::new(vec![0u8; D::OUTPUT_SIZE]);
+    for (counter, chunk) in out.chunks_mut(D::OUTPUT_SIZE).enumerate() {
+        let len = input.len();
+        input[len - 4..].copy_from_slice(&(counter as u32).to_be_bytes());
+        digest(D::default(), &input, &mut block);
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+    Ok(())
+}
+
+///
+/// An object-safe form of [`Digest`], with the block and output sizes available at runtime.
+///
+/// The methods are named apart from those of [`Digest`], so both traits can be imported together.
+///
+/// Every [`Digest`] implements this trait, so a `Box<dyn DynDigest>` can hold any digest chosen at runtime, for example with [`digest_by_name`].
+pub trait DynDigest {
+    fn dyn_block_size(&self) -> usize;
+
+    fn dyn_output_size(&self) -> usize;
+
+    fn dyn_init(&mut self);
+
+    ///
+    /// Processes exactly one block of `dyn_block_size()` bytes
+    fn dyn_update(&mut self, block: &[u8]);
+
+    ///
+    /// Processes the last block of at most `dyn_block_size()` bytes, and writes `dyn_output_size()` bytes of output to `out`
+    fn dyn_finalize(&mut self, lblock: &[u8], out: &mut [u8]);
+}
+
+impl<D: Digest> DynDigest for D {
+    fn dyn_block_size(&self) -> usize {
+        D::BLOCK_SIZE
+    }
+
+    fn dyn_output_size(&self) -> usize {
+        D::OUTPUT_SIZE
+    }
+
+    fn dyn_init(&mut self) {
+        <D as Digest>::init(self)
+    }
+
+    fn dyn_update(&mut self, block: &[u8]) {
+        <D as Digest>::update(self, block)
+    }
+
+    fn dyn_finalize(&mut self, lblock: &[u8], out: &mut [u8]) {
+        <D as Digest>::do_final(self, lblock, out)
+    }
+}
+
+///
+/// Hashes `bytes` with a [`DynDigest`], as [`digest`] does for a statically known digest
+pub fn digest_dyn(digest: &mut dyn DynDigest, bytes: &[u8], out: &mut [u8]) {
+    digest.dyn_init();
+    let mut x = bytes.chunks(digest.dyn_block_size());
+    let last = x.next_back();
+    for block in x {
+        digest.dyn_update(block)
+    }
+
+    digest.dyn_finalize(last.unwrap_or(&[]), out)
+}
+
+///
+/// Constructs the digest named `name`, or returns `None` if no digest by that name is available.
+///
+/// Names are matched case-insensitively. The supported names are `sha1` (with the `sha1` feature), `sha224`, `sha256`, `sha384`, `sha512`, `sha512-224`, and `sha512-256`.
+/// A hyphen after `sha` (as in `sha-256`) is also accepted.
+///
+/// ## Examples
+///
+/// ```
+/// use lc_crypto::digest::{digest_by_name, digest_dyn};
+/// let mut sha = digest_by_name("SHA-256").unwrap();
+/// let mut out = vec![0u8; sha.dyn_output_size()];
+/// digest_dyn(&mut *sha, b"abc", &mut out);
+/// assert_eq!(out[..4], [0xba, 0x78, 0x16, 0xbf]);
+/// ```
+pub fn digest_by_name(name: &str) -> Option<Box<dyn DynDigest>> {
+    let name = name.to_ascii_lowercase();
+    let name = match name.strip_prefix("sha-") {
+        Some(rest) => alloc::format!("sha{}", rest),
+        None => name,
+    };
+    Some(match &*name {
+        #[cfg(feature = "sha1")]
+        "sha1" => Box::new(sha1::Sha1::new()),
+        "sha224" => Box::new(sha2::Sha224::new()),
+        "sha256" => Box::new(sha2::Sha256::new()),
+        "sha384" => Box::new(sha2::Sha384::new()),
+        "sha512" => Box::new(sha2::Sha512::new()),
+        "sha512-224" | "sha512/224" => Box::new(sha2::Sha512_224::new()),
+        "sha512-256" | "sha512/256" => Box::new(sha2::Sha512_256::new()),
+        _ => return None,
+    })
+}
+
+///
+/// Hashes `data` with a default-constructed `D`, returning the output as a vector of `D::OUTPUT_SIZE` bytes.
+///
+/// ## Examples
+///
+/// ```
+/// use lc_crypto::digest::{hash, sha2::Sha256};
+/// let out = hash::<Sha256, _>("hello");
+/// assert_eq!(out.len(), 32);
+/// ```
+pub fn hash<D: Digest + Default, B: AsRef<[u8]>>(data: B) -> Vec<u8> {
+    let mut out = vec![0u8; D::OUTPUT_SIZE];
+    self::digest(D::default(), data.as_ref(), &mut out);
+    out
+}
+
+///
+/// Hashes everything read from `reader` with a default-constructed `D`, returning the output as a vector of `D::OUTPUT_SIZE` bytes.
+///
+/// The stream is read in block-aligned chunks, so it is never held in memory all at once. Reads that fail with [`std::io::ErrorKind::Interrupted`] are retried,
+///  and any other error is returned.
+#[cfg(any(test, feature = "std"))]
+pub fn hash_reader<D: Digest + Default, R: std::io::Read>(
+    mut reader: R,
+) -> std::io::Result<Vec<u8>> {
+    let mut digest = D::default();
+    digest.init();
+    let mut buf = Zeroizing::new(vec![0u8; 64 * D::BLOCK_SIZE]);
+    let mut len = 0;
+    loop {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+        if len == buf.len() {
+            // Keep the last block back, since it may turn out to be the final one
+            let keep = len - D::BLOCK_SIZE;
+            for block in buf[..keep].chunks(D::BLOCK_SIZE) {
+                digest.update(block);
+            }
+            buf.copy_within(keep.., 0);
+            len = D::BLOCK_SIZE;
+        }
+    }
+    let mut blocks = buf[..len].chunks(D::BLOCK_SIZE);
+    let last = blocks.next_back();
+    for block in blocks {
+        digest.update(block);
+    }
+    let mut out = vec![0u8; D::OUTPUT_SIZE];
+    digest.do_final(last.unwrap_or(&[]), &mut out);
+    Ok(out)
+}
+
+///
+/// Adapts a [`Digest`] to [`core::hash::Hasher`], so that any type implementing [`core::hash::Hash`] (including `#[derive(Hash)]` types) can be fed into a cryptographic hash.
+///
+/// Written bytes are buffered into whole blocks before being passed to the digest.
+/// [`Hasher::finish`] only returns the first 8 bytes of the output, as a little-endian `u64`, which discards most of the digest.
+/// Use [`StdHasher::finalize_digest`] to obtain the full output.
+///
+/// The bytes produced by `Hash` impls are not stable across platforms or Rust versions (for example, integers are written in native byte order),
+///  so these hashes should not be persisted or compared between machines.
+pub struct StdHasher<D: Digest> {
+    digest: D,
+    buf: Zeroizing<Vec<u8>>,
+}
+
+impl<D: Digest> StdHasher<D> {
+    pub fn new(mut digest: D) -> Self {
+        digest.init();
+        Self {
+            digest,
+            buf: Zeroizing::new(Vec::with_capacity(D::BLOCK_SIZE)),
+        }
+    }
+
+    ///
+    /// Finishes the digest, returning all `D::OUTPUT_SIZE` bytes of output
+    pub fn finalize_digest(mut self) -> Vec<u8> {
+        let mut out = vec![0u8; D::OUTPUT_SIZE];
+        self.digest.do_final(&self.buf, &mut out);
+        out
+    }
+}
+
+impl<D: Digest + Default> Default for StdHasher<D> {
+    fn default() -> Self {
+        Self::new(D::default())
+    }
+}
+
+impl<D: Digest + Clone> Hasher for StdHasher<D> {
+    fn write(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let len = (D::BLOCK_SIZE - self.buf.len()).min(bytes.len());
+            self.buf.extend_from_slice(&bytes[..len]);
+            bytes = &bytes[len..];
+            if self.buf.len() == D::BLOCK_SIZE {
+                self.digest.update(&self.buf);
+                self.buf.clear();
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut digest = self.digest.clone();
+        let mut out = Zeroizing::new(vec![0u8; D::OUTPUT_SIZE]);
+        digest.do_final(&self.buf, &mut out);
+        let mut bytes = [0u8; 8];
+        let len = out.len().min(8);
+        bytes[..len].copy_from_slice(&out[..len]);
+        u64::from_le_bytes(bytes)
+    }
+}
+
+#[derive(Clone)]
 pub struct Hmac<D: Digest> {
     digest: D,
     key: Zeroizing<Box<[u8]>>,
@@ -80,6 +429,19 @@ impl<D: Digest> Hmac<D> {
             key: inner_key,
         }
     }
+
+    ///
+    /// Replaces the key, leaving `self` in the same state as [`Hmac::new`] would, but reusing the existing key buffer instead of allocating a new one
+    pub fn reset_into(&mut self, key: &[u8]) {
+        if key.len() <= self.key.len() {
+            let len = key.len();
+            self.key[..len].copy_from_slice(key);
+            self.key[len..].fill(0);
+        } else {
+            self::digest(&mut self.digest, key, &mut self.key[..D::OUTPUT_SIZE]);
+            self.key[D::OUTPUT_SIZE..].fill(0);
+        }
+    }
 }
 
 impl<D: Digest> Digest for Hmac<D> {
@@ -125,10 +487,11 @@ mod test {
     use crate::digest::{
         sha1::Sha1,
         sha2::{Sha224, Sha256, Sha512, Sha512_224, Sha512_256},
-        Hmac,
+        Digest, Hmac, KnownAnswer,
     };
 
     use super::sha2::Sha384;
+    use alloc::{boxed::Box, vec, vec::Vec};
 
     #[test]
     fn sha1_test_empty() {
@@ -202,6 +565,19 @@ mod test {
         assert_eq!(out, expected);
     }
 
+    #[test]
+    fn sha1_test_two_block() {
+        // FIPS 180 example; the 56-byte message needs a second padding block
+        let input = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        let mut out = [0; 20];
+        let expected: [u8; 20] = [
+            0x84, 0x98, 0x3e, 0x44, 0x1c, 0x3b, 0xd2, 0x6e, 0xba, 0xae, 0x4a, 0xa1, 0xf9, 0x51,
+            0x29, 0xe5, 0xe5, 0x46, 0x70, 0xf1,
+        ];
+        super::digest(Sha1::new(), input, &mut out);
+        assert_eq!(out, expected);
+    }
+
     #[test]
     fn sha224_test_empty() {
         let input = b"";
@@ -254,6 +630,20 @@ mod test {
         assert_eq!(out, expected);
     }
 
+    #[test]
+    fn sha256_test_two_block() {
+        // FIPS 180-4 example; the 56-byte message needs a second padding block
+        let input = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        let mut out = [0u8; 32];
+        let expected = [
+            0x24, 0x8d, 0x6a, 0x61, 0xd2, 0x06, 0x38, 0xb8, 0xe5, 0xc0, 0x26, 0x93, 0x0c, 0x3e,
+            0x60, 0x39, 0xa3, 0x3c, 0xe4, 0x59, 0x64, 0xff, 0x21, 0x67, 0xf6, 0xec, 0xed, 0xd4,
+            0x19, 0xdb, 0x06, 0xc1,
+        ];
+        super::digest(Sha256::new(), input, &mut out);
+        assert_eq!(out, expected);
+    }
+
     #[test]
     fn sha384_test_empty() {
         let input = b"";
@@ -308,6 +698,60 @@ mod test {
         assert_eq!(out, expected);
     }
 
+    #[test]
+    fn sha512_224_test_abc() {
+        // FIPS 180-4 example
+        let input = b"abc";
+        let mut out = [0u8; 28];
+        let expected = [
+            0x46, 0x34, 0x27, 0x0f, 0x70, 0x7b, 0x6a, 0x54, 0xda, 0xae, 0x75, 0x30, 0x46, 0x08,
+            0x42, 0xe2, 0x0e, 0x37, 0xed, 0x26, 0x5c, 0xee, 0xe9, 0xa4, 0x3e, 0x89, 0x24, 0xaa,
+        ];
+        super::digest(Sha512_224::new(), input, &mut out);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn sha512_224_test_two_block() {
+        // FIPS 180-4 example
+        let input = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+        let mut out = [0u8; 28];
+        let expected = [
+            0x23, 0xfe, 0xc5, 0xbb, 0x94, 0xd6, 0x0b, 0x23, 0x30, 0x81, 0x92, 0x64, 0x0b, 0x0c,
+            0x45, 0x33, 0x35, 0xd6, 0x64, 0x73, 0x4f, 0xe4, 0x0e, 0x72, 0x68, 0x67, 0x4a, 0xf9,
+        ];
+        super::digest(Sha512_224::new(), input, &mut out);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn sha512_256_test_abc() {
+        // FIPS 180-4 example
+        let input = b"abc";
+        let mut out = [0u8; 32];
+        let expected = [
+            0x53, 0x04, 0x8e, 0x26, 0x81, 0x94, 0x1e, 0xf9, 0x9b, 0x2e, 0x29, 0xb7, 0x6b, 0x4c,
+            0x7d, 0xab, 0xe4, 0xc2, 0xd0, 0xc6, 0x34, 0xfc, 0x6d, 0x46, 0xe0, 0xe2, 0xf1, 0x31,
+            0x07, 0xe7, 0xaf, 0x23,
+        ];
+        super::digest(Sha512_256::new(), input, &mut out);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn sha512_256_test_two_block() {
+        // FIPS 180-4 example
+        let input = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+        let mut out = [0u8; 32];
+        let expected = [
+            0x39, 0x28, 0xe1, 0x84, 0xfb, 0x86, 0x90, 0xf8, 0x40, 0xda, 0x39, 0x88, 0x12, 0x1d,
+            0x31, 0xbe, 0x65, 0xcb, 0x9d, 0x3e, 0xf8, 0x3e, 0xe6, 0x14, 0x6f, 0xea, 0xc8, 0x61,
+            0xe1, 0x9b, 0x56, 0x3a,
+        ];
+        super::digest(Sha512_256::new(), input, &mut out);
+        assert_eq!(out, expected);
+    }
+
     #[test]
     fn hmac_sha1_test_smart() {
         let input = b"The quick brown fox jumps over the lazy dog";
@@ -320,4 +764,292 @@ mod test {
         super::digest(Hmac::new(Sha1::new(), key), input, &mut out);
         assert_eq!(out, expected);
     }
+
+    #[test]
+    fn sha256_test_truncated() {
+        let input = b"The quick brown fox jumps over the lazy dog";
+        let mut full = [0u8; 32];
+        super::digest(Sha256::new(), input, &mut full);
+        let mut sha256 = Sha256::new();
+        sha256.init();
+        let mut out = [0u8; 16];
+        sha256.do_final_truncated(input, &mut out);
+        assert_eq!(out, full[..16]);
+    }
+
+    #[test]
+    fn sha256_test_hash() {
+        let expected = [
+            0x2c, 0xf2, 0x4d, 0xba, 0x5f, 0xb0, 0xa3, 0x0e, 0x26, 0xe8, 0x3b, 0x2a, 0xc5, 0xb9,
+            0xe2, 0x9e, 0x1b, 0x16, 0x1e, 0x5c, 0x1f, 0xa7, 0x42, 0x5e, 0x73, 0x04, 0x33, 0x62,
+            0x93, 0x8b, 0x98, 0x24,
+        ];
+        assert_eq!(super::hash::<Sha256, _>("hello"), expected);
+        let v: Vec<u8> = b"hello".to_vec();
+        assert_eq!(super::hash::<Sha256, _>(v), expected);
+        assert_eq!(super::hash::<Sha256, _>(b"hello"), expected);
+        assert_eq!(super::hash::<Sha256, _>(&b"hello"[..]), expected);
+    }
+
+    #[test]
+    fn std_hasher_tuple() {
+        use core::hash::{Hash, Hasher};
+
+        let mut hasher = super::StdHasher::new(Sha256::new());
+        let mut expected_input = Vec::new();
+        for i in 0..20u32 {
+            (i, u64::from(i) << 40, i as u8).hash(&mut hasher);
+            expected_input.extend_from_slice(&i.to_ne_bytes());
+            expected_input.extend_from_slice(&(u64::from(i) << 40).to_ne_bytes());
+            expected_input.push(i as u8);
+        }
+        let mut expected = [0u8; 32];
+        super::digest(Sha256::new(), &expected_input, &mut expected);
+
+        let mut first = [0u8; 8];
+        first.copy_from_slice(&expected[..8]);
+        assert_eq!(hasher.finish(), u64::from_le_bytes(first));
+        assert_eq!(hasher.finalize_digest(), expected);
+    }
+
+    #[test]
+    fn digest_size_accessors() {
+        let sha256 = Sha256::default();
+        assert_eq!(sha256.block_size(), 64);
+        assert_eq!(sha256.output_size(), 32);
+        let sha384 = Box::new(Sha384::default());
+        assert_eq!(sha384.block_size(), 128);
+        assert_eq!(sha384.output_size(), 48);
+    }
+
+    fn check_default<D: Digest + Default>(new: D) {
+        let mut expected = vec![0u8; D::OUTPUT_SIZE];
+        super::digest(new, b"abc", &mut expected);
+        assert_eq!(super::hash::<D, _>(b"abc"), expected);
+    }
+
+    #[test]
+    fn digest_default_matches_new() {
+        check_default(Sha1::new());
+        check_default(Sha224::new());
+        check_default(Sha256::new());
+        check_default(Sha384::new());
+        check_default(Sha512::new());
+        check_default(Sha512_224::new());
+        check_default(Sha512_256::new());
+    }
+
+    #[test]
+    fn digest_reset_matches_fresh() {
+        let msgs: [&[u8]; 4] = [b"", b"abc", &[0x5a; 64], &[0xa5; 200]];
+        let mut sha = Sha256::new();
+        let mut hmac = Hmac::new(Sha256::new(), b"initial key");
+        for (i, msg) in msgs.iter().enumerate() {
+            let mut out = [0u8; 32];
+            let mut expected = [0u8; 32];
+            super::digest(&mut sha, msg, &mut out);
+            super::digest(Sha256::new(), msg, &mut expected);
+            assert_eq!(out, expected);
+
+            let key = vec![i as u8; 40 * i];
+            hmac.reset_into(&key);
+            super::digest(&mut hmac, msg, &mut out);
+            super::digest(Hmac::new(Sha256::new(), &key), msg, &mut expected);
+            assert_eq!(out, expected);
+        }
+    }
+
+    #[test]
+    fn dyn_digest_in_scope_with_digest() {
+        // Method calls on a concrete digest stay unambiguous with both traits imported
+        use super::DynDigest;
+        let mut sha = Sha256::new();
+        sha.init();
+        assert_eq!(sha.output_size(), 32);
+        assert_eq!(sha.block_size(), 64);
+        assert_eq!(sha.dyn_output_size(), 32);
+    }
+
+    #[test]
+    fn digest_by_name_dispatch() {
+        fn check<D: Digest>(name: &str, digest: D) {
+            let input = [0x61u8; 300];
+            let mut expected = vec![0u8; D::OUTPUT_SIZE];
+            super::digest(digest, &input, &mut expected);
+            let mut dynamic = super::digest_by_name(name).unwrap();
+            assert_eq!(dynamic.dyn_output_size(), D::OUTPUT_SIZE);
+            assert_eq!(dynamic.dyn_block_size(), D::BLOCK_SIZE);
+            let mut out = vec![0u8; dynamic.dyn_output_size()];
+            super::digest_dyn(&mut *dynamic, &input, &mut out);
+            assert_eq!(out, expected, "{}", name);
+        }
+        check("sha1", Sha1::new());
+        check("SHA-224", Sha224::new());
+        check("sha256", Sha256::new());
+        check("sha384", Sha384::new());
+        check("Sha512", Sha512::new());
+        check("sha512-224", Sha512_224::new());
+        check("SHA-512/256", Sha512_256::new());
+        assert!(super::digest_by_name("md5").is_none());
+        assert!(super::digest_by_name("").is_none());
+    }
+
+    #[test]
+    pub fn digest_self_test() {
+        super::self_test::<Sha1>().unwrap();
+        super::self_test::<Sha224>().unwrap();
+        super::self_test::<Sha256>().unwrap();
+        super::self_test::<Sha384>().unwrap();
+        super::self_test::<Sha512>().unwrap();
+        super::self_test::<Sha512_224>().unwrap();
+        super::self_test::<Sha512_256>().unwrap();
+    }
+
+    // SHA-256 claiming the known answer of SHA-512/256, standing in for a corrupted implementation
+    #[derive(Default)]
+    struct Mislabelled(Sha256);
+
+    impl Digest for Mislabelled {
+        const OUTPUT_SIZE: usize = 32;
+        const BLOCK_SIZE: usize = 64;
+        fn init(&mut self) {
+            self.0.init()
+        }
+        fn update(&mut self, block: &[u8]) {
+            self.0.update(block)
+        }
+        fn do_final(&mut self, lblock: &[u8], out: &mut [u8]) {
+            self.0.do_final(lblock, out)
+        }
+    }
+
+    impl KnownAnswer for Mislabelled {
+        const KAT_OUTPUT: &'static [u8] = Sha512_256::KAT_OUTPUT;
+    }
+
+    #[test]
+    pub fn digest_self_test_failure() {
+        assert_eq!(
+            super::self_test::<Mislabelled>().unwrap_err().kind(),
+            crate::error::ErrorKind::Other
+        );
+    }
+
+    #[test]
+    pub fn mgf1_known_masks() {
+        // Expected masks computed independently with Python's hashlib
+        let mut out = [0u8; 5];
+        super::mgf1::<Sha1>(b"foo", &mut out[..3]).unwrap();
+        assert_eq!(out[..3], [0x1a, 0xc9, 0x07]);
+        super::mgf1::<Sha1>(b"foo", &mut out).unwrap();
+        assert_eq!(out, [0x1a, 0xc9, 0x07, 0x5c, 0xd4]);
+
+        let mut out = [0u8; 50];
+        super::mgf1::<Sha1>(b"bar", &mut out).unwrap();
+        assert_eq!(
+            out,
+            [
+                0xbc, 0x0c, 0x65, 0x5e, 0x01, 0x6b, 0xc2, 0x93, 0x1d, 0x85, 0xa2, 0xe6, 0x75, 0x18,
+                0x1a, 0xdc, 0xef, 0x7f, 0x58, 0x1f, 0x76, 0xdf, 0x27, 0x39, 0xda, 0x74, 0xfa, 0xac,
+                0x41, 0x62, 0x7b, 0xe2, 0xf7, 0xf4, 0x15, 0xc8, 0x9e, 0x98, 0x3f, 0xd0, 0xce, 0x80,
+                0xce, 0xd9, 0x87, 0x86, 0x41, 0xcb, 0x48, 0x76,
+            ]
+        );
+        super::mgf1::<Sha256>(b"bar", &mut out).unwrap();
+        assert_eq!(
+            out,
+            [
+                0x38, 0x25, 0x76, 0xa7, 0x84, 0x10, 0x21, 0xcc, 0x28, 0xfc, 0x4c, 0x09, 0x48, 0x75,
+                0x3f, 0xb8, 0x31, 0x20, 0x90, 0xce, 0xa9, 0x42, 0xea, 0x4c, 0x4e, 0x73, 0x5d, 0x10,
+                0xdc, 0x72, 0x4b, 0x15, 0x5f, 0x9f, 0x60, 0x69, 0xf2, 0x89, 0xd6, 0x1d, 0xac, 0xa0,
+                0xcb, 0x81, 0x45, 0x02, 0xef, 0x04, 0xea, 0xe1,
+            ]
+        );
+    }
+
+    #[test]
+    fn digest_wrong_lengths_are_errors() {
+        use crate::error::ErrorKind;
+
+        let mut sha1 = Sha1::new();
+        assert_eq!(
+            sha1.try_update(&[0; 63]).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        let mut sha256 = Sha256::new();
+        sha256.init();
+        assert_eq!(
+            sha256.try_update(&[0; 65]).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            sha256
+                .try_do_final(&[0; 65], &mut [0; 32])
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            sha256
+                .try_do_final(b"abc", &mut [0; 31])
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            super::try_digest(Sha256::new(), b"abc", &mut [0; 33])
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidInput
+        );
+
+        let mut out = [0u8; 32];
+        super::try_digest(Sha256::new(), b"abc", &mut out).unwrap();
+        assert_eq!(out, Sha256::KAT_OUTPUT);
+        sha256.init();
+        sha256.try_do_final(b"abc", &mut out).unwrap();
+        assert_eq!(out, Sha256::KAT_OUTPUT);
+    }
+
+    ///
+    /// A reader which returns short reads of varying length, and is interrupted now and then
+    struct ShortReads<'a> {
+        data: &'a [u8],
+        calls: usize,
+    }
+
+    impl std::io::Read for ShortReads<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.calls += 1;
+            if self.calls % 7 == 0 {
+                return Err(std::io::ErrorKind::Interrupted.into());
+            }
+            let n = (1 + self.calls * 37 % 1000)
+                .min(buf.len())
+                .min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn hash_reader_matches_in_memory() {
+        let data: Vec<u8> = (0..1_000_003u32).map(|i| (i * 7 + i / 251) as u8).collect();
+        let reader = ShortReads {
+            data: &data,
+            calls: 0,
+        };
+        assert_eq!(
+            super::hash_reader::<Sha256, _>(reader).unwrap(),
+            super::hash::<Sha256, _>(&data)
+        );
+
+        for len in [0, 1, 127, 128, 129, 128 * 64, 128 * 64 + 1] {
+            assert_eq!(
+                super::hash_reader::<Sha512, _>(&data[..len]).unwrap(),
+                super::hash::<Sha512, _>(&data[..len])
+            );
+        }
+    }
 }
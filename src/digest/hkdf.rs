@@ -0,0 +1,217 @@
+//!
+//! The HKDF key derivation function of RFC 5869, built on [`Hmac`].
+
+use alloc::{vec, vec::Vec};
+use zeroize::Zeroizing;
+
+use super::{digest, Digest, Hmac};
+use crate::error::{Error, ErrorKind, Result};
+
+///
+/// HKDF-Extract: derives a pseudorandom key of `D::OUTPUT_SIZE` bytes from the input keying material `ikm` and `salt`
+///
+/// An empty `salt` means the salt is not provided, and is replaced by `D::OUTPUT_SIZE` zero bytes as RFC 5869 specifies.
+pub fn hkdf_extract<D: Digest + Default>(salt: &[u8], ikm: &[u8]) -> Zeroizing<Vec<u8>> {
+    let zeros = vec![0u8; D::OUTPUT_SIZE];
+    let salt = if salt.is_empty() { &zeros[..] } else { salt };
+    let mut prk = Zeroizing::new(vec![0u8; D::OUTPUT_SIZE]);
+    digest(Hmac::new(D::default(), salt), ikm, &mut prk);
+    prk
+}
+
+///
+/// HKDF-Expand: fills `out` with output keying material derived from the pseudorandom key `prk` and `info`.
+///
+/// Returns an error of kind [`ErrorKind::InvalidInput`] if `out` is longer than `255*D::OUTPUT_SIZE` bytes.
+pub fn hkdf_expand<D: Digest + Default>(prk: &[u8], info: &[u8], out: &mut [u8]) -> Result<()> {
+    HkdfExpandReader::<D>::new(prk, info).fill(out)
+}
+
+///
+/// Produces the output of HKDF-Expand incrementally, computing each block only when it is needed.
+///
+/// Reading `n` bytes in any number of calls gives the same bytes as [`hkdf_expand`] into a buffer of `n` bytes.
+/// At most `255*D::OUTPUT_SIZE` bytes are available in total. Under the `std` feature this implements `std::io::Read`, which reports the end of the output as end-of-file.
+pub struct HkdfExpandReader<D: Digest> {
+    hmac: Hmac<D>,
+    info: Vec<u8>,
+    block: Zeroizing<Vec<u8>>,
+    counter: u8,
+    pos: usize,
+}
+
+impl<D: Digest + Default> HkdfExpandReader<D> {
+    pub fn new(prk: &[u8], info: &[u8]) -> Self {
+        Self {
+            hmac: Hmac::new(D::default(), prk),
+            info: info.to_vec(),
+            block: Zeroizing::new(vec![0u8; D::OUTPUT_SIZE]),
+            counter: 0,
+            pos: D::OUTPUT_SIZE,
+        }
+    }
+}
+
+impl<D: Digest> HkdfExpandReader<D> {
+    ///
+    /// The number of bytes that can still be read
+    pub fn remaining(&self) -> usize {
+        (255 - usize::from(self.counter)) * D::OUTPUT_SIZE + (D::OUTPUT_SIZE - self.pos)
+    }
+
+    fn next_block(&mut self) {
+        self.counter += 1;
+        let mut input = Zeroizing::new(Vec::with_capacity(D::OUTPUT_SIZE + self.info.len() + 1));
+        if self.counter > 1 {
+            input.extend_from_slice(&self.block);
+        }
+        input.extend_from_slice(&self.info);
+        input.push(self.counter);
+        digest(&mut self.hmac, &input, &mut self.block);
+        self.pos = 0;
+    }
+
+    ///
+    /// Reads up to `out.len()` bytes, returning the number read, which is less than `out.len()` only once the output is exhausted
+    pub fn read_some(&mut self, out: &mut [u8]) -> usize {
+        let mut len = 0;
+        while len < out.len() {
+            if self.pos == D::OUTPUT_SIZE {
+                if self.counter == 255 {
+                    break;
+                }
+                self.next_block();
+            }
+            let n = (D::OUTPUT_SIZE - self.pos).min(out.len() - len);
+            out[len..len + n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+            self.pos += n;
+            len += n;
+        }
+        len
+    }
+
+    ///
+    /// Fills `out` with the next `out.len()` bytes of output.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidInput`], without reading anything, if fewer than `out.len()` bytes remain.
+    pub fn fill(&mut self, out: &mut [u8]) -> Result<()> {
+        if out.len() > self.remaining() {
+            return Err(Error::new_with_message(
+                ErrorKind::InvalidInput,
+                "output too long for HKDF-Expand",
+            ));
+        }
+        self.read_some(out);
+        Ok(())
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<D: Digest> std::io::Read for HkdfExpandReader<D> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(self.read_some(buf))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+
+    use super::{hkdf_expand, hkdf_extract, HkdfExpandReader};
+    use crate::{digest::sha2::Sha256, error::ErrorKind};
+
+    const IKM: [u8; 22] = [0x0b; 22];
+    const SALT: [u8; 13] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+    const INFO: [u8; 10] = [0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9];
+
+    #[test]
+    pub fn hkdf_rfc5869_case1() {
+        // RFC 5869 appendix A.1
+        let prk = hkdf_extract::<Sha256>(&SALT, &IKM);
+        assert_eq!(
+            *prk,
+            [
+                0x07, 0x77, 0x09, 0x36, 0x2c, 0x2e, 0x32, 0xdf, 0x0d, 0xdc, 0x3f, 0x0d, 0xc4, 0x7b,
+                0xba, 0x63, 0x90, 0xb6, 0xc7, 0x3b, 0xb5, 0x0f, 0x9c, 0x31, 0x22, 0xec, 0x84, 0x4a,
+                0xd7, 0xc2, 0xb3, 0xe5
+            ]
+        );
+        let mut okm = [0u8; 42];
+        hkdf_expand::<Sha256>(&prk, &INFO, &mut okm).unwrap();
+        assert_eq!(
+            okm,
+            [
+                0x3c, 0xb2, 0x5f, 0x25, 0xfa, 0xac, 0xd5, 0x7a, 0x90, 0x43, 0x4f, 0x64, 0xd0, 0x36,
+                0x2f, 0x2a, 0x2d, 0x2d, 0x0a, 0x90, 0xcf, 0x1a, 0x5a, 0x4c, 0x5d, 0xb0, 0x2d, 0x56,
+                0xec, 0xc4, 0xc5, 0xbf, 0x34, 0x00, 0x72, 0x08, 0xd5, 0xb8, 0x87, 0x18, 0x58, 0x65
+            ]
+        );
+    }
+
+    #[test]
+    pub fn hkdf_rfc5869_case3() {
+        // RFC 5869 appendix A.3: salt and info not provided
+        let prk = hkdf_extract::<Sha256>(&[], &IKM);
+        assert_eq!(
+            *prk,
+            [
+                0x19, 0xef, 0x24, 0xa3, 0x2c, 0x71, 0x7b, 0x16, 0x7f, 0x33, 0xa9, 0x1d, 0x6f, 0x64,
+                0x8b, 0xdf, 0x96, 0x59, 0x67, 0x76, 0xaf, 0xdb, 0x63, 0x77, 0xac, 0x43, 0x4c, 0x1c,
+                0x29, 0x3c, 0xcb, 0x04
+            ]
+        );
+        assert_eq!(prk, hkdf_extract::<Sha256>(&[0; 32], &IKM));
+        let mut okm = [0u8; 42];
+        hkdf_expand::<Sha256>(&prk, &[], &mut okm).unwrap();
+        assert_eq!(
+            okm,
+            [
+                0x8d, 0xa4, 0xe7, 0x75, 0xa5, 0x63, 0xc1, 0x8f, 0x71, 0x5f, 0x80, 0x2a, 0x06, 0x3c,
+                0x5a, 0x31, 0xb8, 0xa1, 0x1f, 0x5c, 0x5e, 0xe1, 0x87, 0x9e, 0xc3, 0x45, 0x4e, 0x5f,
+                0x3c, 0x73, 0x8d, 0x2d, 0x9d, 0x20, 0x13, 0x95, 0xfa, 0xa4, 0xb6, 0x1a, 0x96, 0xc8
+            ]
+        );
+    }
+
+    #[test]
+    pub fn hkdf_reader_incremental() {
+        let prk = hkdf_extract::<Sha256>(&SALT, &IKM);
+        let mut expected = [0u8; 200];
+        hkdf_expand::<Sha256>(&prk, &INFO, &mut expected).unwrap();
+        let mut reader = HkdfExpandReader::<Sha256>::new(&prk, &INFO);
+        let mut got = [0u8; 200];
+        let mut pos = 0;
+        for len in [1, 7, 31, 32, 33, 0, 64, 32].iter().cycle() {
+            let len = (*len).min(200 - pos);
+            reader.read_exact(&mut got[pos..pos + len]).unwrap();
+            pos += len;
+            if pos == 200 {
+                break;
+            }
+        }
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    pub fn hkdf_expand_limit() {
+        let prk = [0x11; 32];
+        let mut out = vec![0u8; 255 * 32 + 1];
+        assert_eq!(
+            hkdf_expand::<Sha256>(&prk, b"", &mut out)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidInput
+        );
+        hkdf_expand::<Sha256>(&prk, b"", &mut out[..255 * 32]).unwrap();
+
+        let mut reader = HkdfExpandReader::<Sha256>::new(&prk, b"");
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).unwrap();
+        assert_eq!(all, out[..255 * 32]);
+        assert_eq!(reader.remaining(), 0);
+        assert_eq!(
+            reader.read_exact(&mut [0u8; 1]).unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+}
@@ -0,0 +1,6 @@
+//!
+//! Digests which hash several independent messages at once, using one SIMD lane per message.
+//!
+//! These are useful when many short messages of the same length must be hashed, such as when verifying a batch of tokens.
+
+pub mod sha256_x8;
@@ -0,0 +1,228 @@
+use crate::digest::sha2::Sha256;
+
+///
+/// Hashes 8 messages of equal length with SHA-256, returning the 8 digests in the same order.
+///
+/// On x86_64 processors supporting AVX2, the messages are hashed in parallel, one per 32-bit lane.
+/// Otherwise, this falls back to hashing them one after another.
+///
+/// ## Panics
+///
+/// Panics if the messages do not all have the same length.
+///
+/// ## Examples
+///
+/// ```
+/// use lc_crypto::digest::multibuffer::sha256_x8::hash_many;
+/// let msgs: [&[u8]; 8] = [b"token-0", b"token-1", b"token-2", b"token-3", b"token-4", b"token-5", b"token-6", b"token-7"];
+/// let digests = hash_many(&msgs);
+/// assert_eq!(digests[3], lc_crypto::digest::hash::<lc_crypto::digest::sha2::Sha256, _>(b"token-3")[..]);
+/// ```
+pub fn hash_many(inputs: &[&[u8]; 8]) -> [[u8; 32]; 8] {
+    let len = inputs[0].len();
+    assert!(
+        inputs.iter().all(|i| i.len() == len),
+        "all messages must have the same length"
+    );
+    #[cfg(target_arch = "x86_64")]
+    if avx2::is_available() {
+        return avx2::hash_many(inputs);
+    }
+    hash_many_scalar(inputs)
+}
+
+fn hash_many_scalar(inputs: &[&[u8]; 8]) -> [[u8; 32]; 8] {
+    let mut out = [[0u8; 32]; 8];
+    for (o, i) in out.iter_mut().zip(inputs) {
+        crate::digest::digest(Sha256::new(), i, o);
+    }
+    out
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use core::arch::x86_64::*;
+
+    use zeroize::Zeroizing;
+
+    use crate::digest::sha2::{K32, SHA256_IV};
+
+    pub fn is_available() -> bool {
+        crate::cpu_feature_detected!("avx2")
+    }
+
+    #[allow(unsafe_code)]
+    pub fn hash_many(inputs: &[&[u8]; 8]) -> [[u8; 32]; 8] {
+        assert!(is_available());
+        // SAFETY:
+        // We just checked that the processor supports AVX2
+        unsafe { hash_many_avx2(inputs) }
+    }
+
+    // The functions below may only be called once AVX2 support has been checked
+    #[allow(unsafe_code)]
+    #[target_feature(enable = "avx2")]
+    unsafe fn ror(x: __m256i, n: i32) -> __m256i {
+        _mm256_or_si256(
+            _mm256_srl_epi32(x, _mm_cvtsi32_si128(n)),
+            _mm256_sll_epi32(x, _mm_cvtsi32_si128(32 - n)),
+        )
+    }
+
+    #[allow(unsafe_code)]
+    #[target_feature(enable = "avx2")]
+    unsafe fn add(a: __m256i, b: __m256i) -> __m256i {
+        _mm256_add_epi32(a, b)
+    }
+
+    #[allow(unsafe_code)]
+    #[target_feature(enable = "avx2")]
+    unsafe fn xor3(a: __m256i, b: __m256i, c: __m256i) -> __m256i {
+        _mm256_xor_si256(_mm256_xor_si256(a, b), c)
+    }
+
+    #[allow(unsafe_code)]
+    #[target_feature(enable = "avx2")]
+    unsafe fn compress8(h: &mut [__m256i; 8], blocks: [&[u8]; 8]) {
+        let mut w = [_mm256_setzero_si256(); 64];
+        for (t, wt) in w.iter_mut().enumerate().take(16) {
+            let word = |lane: usize| {
+                let b = &blocks[lane][4 * t..];
+                u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as i32
+            };
+            *wt = _mm256_set_epi32(
+                word(7),
+                word(6),
+                word(5),
+                word(4),
+                word(3),
+                word(2),
+                word(1),
+                word(0),
+            );
+        }
+        for t in 16..64 {
+            let s0 = xor3(
+                ror(w[t - 15], 7),
+                ror(w[t - 15], 18),
+                _mm256_srli_epi32::<3>(w[t - 15]),
+            );
+            let s1 = xor3(
+                ror(w[t - 2], 17),
+                ror(w[t - 2], 19),
+                _mm256_srli_epi32::<10>(w[t - 2]),
+            );
+            w[t] = add(add(w[t - 16], s0), add(w[t - 7], s1));
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut l] = *h;
+        for (k, wt) in K32.iter().zip(w) {
+            let s1 = xor3(ror(e, 6), ror(e, 11), ror(e, 25));
+            let ch = _mm256_xor_si256(_mm256_and_si256(e, f), _mm256_andnot_si256(e, g));
+            let temp1 = add(add(add(l, s1), add(ch, _mm256_set1_epi32(*k as i32))), wt);
+            let s0 = xor3(ror(a, 2), ror(a, 13), ror(a, 22));
+            let maj = xor3(
+                _mm256_and_si256(a, b),
+                _mm256_and_si256(a, c),
+                _mm256_and_si256(b, c),
+            );
+            let temp2 = add(s0, maj);
+
+            l = g;
+            g = f;
+            f = e;
+            e = add(d, temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = add(temp1, temp2);
+        }
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e, f, g, l]) {
+            *h = add(*h, v);
+        }
+    }
+
+    #[allow(unsafe_code)]
+    #[target_feature(enable = "avx2")]
+    unsafe fn hash_many_avx2(inputs: &[&[u8]; 8]) -> [[u8; 32]; 8] {
+        let mut h = SHA256_IV.map(|v| _mm256_set1_epi32(v as i32));
+        let len = inputs[0].len();
+        for i in 0..(len / 64) {
+            compress8(&mut h, inputs.map(|m| &m[64 * i..][..64]));
+        }
+
+        // All messages have the same length, so they all need the same number of padding blocks
+        let rem = len % 64;
+        let tail_len = if rem < 56 { 64 } else { 128 };
+        let mut tails = Zeroizing::new([[0u8; 128]; 8]);
+        for (t, m) in tails.iter_mut().zip(inputs) {
+            t[..rem].copy_from_slice(&m[len - rem..]);
+            t[rem] = 0x80;
+            t[tail_len - 8..tail_len].copy_from_slice(&((len as u64) * 8).to_be_bytes());
+        }
+        for i in 0..(tail_len / 64) {
+            let blocks = core::array::from_fn(|lane| &tails[lane][64 * i..][..64]);
+            compress8(&mut h, blocks);
+        }
+
+        let mut out = [[0u8; 32]; 8];
+        for (j, v) in h.iter().enumerate() {
+            let lanes: [u32; 8] = bytemuck::cast(*v);
+            for (o, lane) in out.iter_mut().zip(lanes) {
+                o[4 * j..][..4].copy_from_slice(&lane.to_be_bytes());
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec::Vec;
+
+    use super::{hash_many, hash_many_scalar};
+    use crate::digest::sha2::Sha256;
+
+    fn check(len: usize) {
+        let msgs: Vec<Vec<u8>> = (0..8u8)
+            .map(|lane| {
+                (0..len)
+                    .map(|i| (i as u8).wrapping_mul(31) ^ lane)
+                    .collect()
+            })
+            .collect();
+        let inputs: [&[u8]; 8] = core::array::from_fn(|i| &msgs[i][..]);
+        let out = hash_many(&inputs);
+        assert_eq!(out, hash_many_scalar(&inputs));
+        for (o, m) in out.iter().zip(&msgs) {
+            let mut expected = [0u8; 32];
+            crate::digest::digest(Sha256::new(), m, &mut expected);
+            assert_eq!(*o, expected);
+        }
+    }
+
+    #[test]
+    pub fn sha256_x8_matches_scalar() {
+        for len in [0, 1, 55, 56, 63, 64, 65, 119, 120, 128, 1000] {
+            check(len);
+        }
+    }
+
+    #[test]
+    pub fn sha256_x8_known_answer() {
+        let inputs: [&[u8]; 8] = [b"abc"; 8];
+        let expected = [
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+            0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+            0xf2, 0x00, 0x15, 0xad,
+        ];
+        assert_eq!(hash_many(&inputs), [expected; 8]);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn sha256_x8_unequal_lengths() {
+        let inputs: [&[u8]; 8] = [b"a", b"a", b"a", b"a", b"a", b"a", b"a", b"ab"];
+        hash_many(&inputs);
+    }
+}
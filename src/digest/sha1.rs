@@ -2,7 +2,7 @@ use core::convert::TryInto;
 
 use zeroize::{Zeroize, Zeroizing};
 
-use super::Digest;
+use super::{Digest, KnownAnswer};
 
 #[cfg(target_arch = "x86_64")]
 mod x86_64;
@@ -18,6 +18,7 @@ mod x86_64;
 /// The algorithms exported from [`lc_crypto::digest::sha2`] are more secure and should be used instead where security is desired or required.
 ///
 /// This algorithm is deprecated for security purposes, but may be used where security is not necessary
+#[derive(Clone)]
 pub struct Sha1 {
     h: [u32; 5],
     size: u64,
@@ -38,6 +39,13 @@ impl Default for Sha1 {
     }
 }
 
+impl KnownAnswer for Sha1 {
+    const KAT_OUTPUT: &'static [u8] = &[
+        0xa9, 0x99, 0x3e, 0x36, 0x47, 0x06, 0x81, 0x6a, 0xba, 0x3e, 0x25, 0x71, 0x78, 0x50, 0xc2,
+        0x6c, 0x9c, 0xd0, 0xd8, 0x9d,
+    ];
+}
+
 impl Digest for Sha1 {
     const OUTPUT_SIZE: usize = 20;
     const BLOCK_SIZE: usize = 64;
@@ -106,7 +114,7 @@ impl Digest for Sha1 {
         self.size += (lblock.len() as u64) * 8;
         let ml = self.size;
         bytes[len] = 0x80;
-        if (64 - len) < 8 {
+        if (64 - len) < 9 {
             self.update(&bytes);
             bytes = [0u8; 64];
         }
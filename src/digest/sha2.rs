@@ -1,21 +1,20 @@
-use alloc::format;
-use zeroize::Zeroize;
-
-use super::Digest;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{Digest, KnownAnswer};
+use crate::error::{Error, ErrorKind, Result};
+
+pub(crate) const K32: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
 
 fn do_sha32_block(block: &[u8], h: &mut [u32; 8]) {
-    let k = [
-        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4,
-        0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe,
-        0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f,
-        0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7,
-        0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc,
-        0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
-        0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116,
-        0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
-        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7,
-        0xc67178f2,
-    ];
     let block = bytemuck::cast_slice::<u8, [u8; 4]>(block);
     let mut w = [0u32; 64];
     for i in 0..16 {
@@ -46,7 +45,7 @@ fn do_sha32_block(block: &[u8], h: &mut [u32; 8]) {
         let temp1 = l
             .wrapping_add(s1)
             .wrapping_add(ch)
-            .wrapping_add(k[i])
+            .wrapping_add(K32[i])
             .wrapping_add(w[i]);
         let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
         let maj = (a & b) ^ (a & c) ^ (b & c);
@@ -72,6 +71,15 @@ fn do_sha32_block(block: &[u8], h: &mut [u32; 8]) {
     h[7] = h[7].wrapping_add(l);
 }
 
+const SHA224_IV: [u32; 8] = [
+    0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4,
+];
+
+pub(crate) const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+#[derive(Clone)]
 pub struct Sha32<const BITS: usize> {
     h: [u32; 8],
     size: u64,
@@ -80,10 +88,7 @@ pub struct Sha32<const BITS: usize> {
 impl Sha32<224> {
     pub const fn new() -> Self {
         Self {
-            h: [
-                0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7,
-                0xbefa4fa4,
-            ],
+            h: SHA224_IV,
             size: 0,
         }
     }
@@ -92,15 +97,49 @@ impl Sha32<224> {
 impl Sha32<256> {
     pub const fn new() -> Self {
         Self {
-            h: [
-                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
-                0x5be0cd19,
-            ],
+            h: SHA256_IV,
             size: 0,
         }
     }
 }
 
+impl<const BITS: usize> Sha32<BITS> {
+    ///
+    /// Exports the state after the blocks absorbed so far, as the 8 hash words followed by the message length in bits, all big-endian.
+    ///
+    /// The state determines the digest of any extension of the message, and in an [`crate::digest::Hmac`] it stands in for the key, so it must be protected like the data that was hashed.
+    /// Any bytes the caller has buffered towards the next block are not part of the state.
+    pub fn export_state(&self) -> Zeroizing<[u8; 40]> {
+        let mut out = Zeroizing::new([0u8; 40]);
+        for (o, h) in out.chunks_mut(4).zip(self.h) {
+            o.copy_from_slice(&h.to_be_bytes());
+        }
+        out[32..].copy_from_slice(&self.size.to_be_bytes());
+        out
+    }
+
+    ///
+    /// Restores a state produced by [`Sha32::export_state`], to continue hashing from the next block.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidData`] if the length is not a whole number of blocks.
+    pub fn import_state(state: &[u8; 40]) -> Result<Self> {
+        let mut size = [0u8; 8];
+        size.copy_from_slice(&state[32..]);
+        let size = u64::from_be_bytes(size);
+        if size % 512 != 0 {
+            return Err(Error::new_with_message(
+                ErrorKind::InvalidData,
+                "SHA-256 state length is not a whole number of blocks",
+            ));
+        }
+        let mut h = [0u32; 8];
+        for (h, b) in h.iter_mut().zip(state.chunks(4)) {
+            *h = u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+        }
+        Ok(Self { h, size })
+    }
+}
+
 impl Default for Sha32<224> {
     fn default() -> Self {
         Self::new()
@@ -121,20 +160,14 @@ impl<const BITS: usize> Digest for Sha32<BITS> {
         // This would work so well with specialization
         self.size = 0;
         self.h = match BITS {
-            224 => [
-                0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7,
-                0xbefa4fa4,
-            ],
-            256 => [
-                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
-                0x5be0cd19,
-            ],
+            224 => SHA224_IV,
+            256 => SHA256_IV,
             _ => unreachable!(),
         }
     }
 
     fn update(&mut self, block: &[u8]) {
-        self.size = block.len() as u64 * 8;
+        self.size += 512;
         do_sha32_block(block, &mut self.h)
     }
 
@@ -150,7 +183,7 @@ impl<const BITS: usize> Digest for Sha32<BITS> {
         self.size += (lblock.len() as u64) * 8;
         let ml = self.size;
         bytes[len] = 0x80;
-        if (64 - len) < 8 {
+        if (64 - len) < 9 {
             self.update(&bytes);
             bytes = [0u8; 64];
         }
@@ -168,103 +201,123 @@ impl<const BITS: usize> Digest for Sha32<BITS> {
 pub type Sha224 = Sha32<224>;
 pub type Sha256 = Sha32<256>;
 
-fn do_sha64_block(block: &[u8], h: &mut [u64; 8]) {
-    let k = [
-        0x428a2f98d728ae22,
-        0x7137449123ef65cd,
-        0xb5c0fbcfec4d3b2f,
-        0xe9b5dba58189dbbc,
-        0x3956c25bf348b538,
-        0x59f111f1b605d019,
-        0x923f82a4af194f9b,
-        0xab1c5ed5da6d8118,
-        0xd807aa98a3030242,
-        0x12835b0145706fbe,
-        0x243185be4ee4b28c,
-        0x550c7dc3d5ffb4e2,
-        0x72be5d74f27b896f,
-        0x80deb1fe3b1696b1,
-        0x9bdc06a725c71235,
-        0xc19bf174cf692694,
-        0xe49b69c19ef14ad2,
-        0xefbe4786384f25e3,
-        0x0fc19dc68b8cd5b5,
-        0x240ca1cc77ac9c65,
-        0x2de92c6f592b0275,
-        0x4a7484aa6ea6e483,
-        0x5cb0a9dcbd41fbd4,
-        0x76f988da831153b5,
-        0x983e5152ee66dfab,
-        0xa831c66d2db43210,
-        0xb00327c898fb213f,
-        0xbf597fc7beef0ee4,
-        0xc6e00bf33da88fc2,
-        0xd5a79147930aa725,
-        0x06ca6351e003826f,
-        0x142929670a0e6e70,
-        0x27b70a8546d22ffc,
-        0x2e1b21385c26c926,
-        0x4d2c6dfc5ac42aed,
-        0x53380d139d95b3df,
-        0x650a73548baf63de,
-        0x766a0abb3c77b2a8,
-        0x81c2c92e47edaee6,
-        0x92722c851482353b,
-        0xa2bfe8a14cf10364,
-        0xa81a664bbc423001,
-        0xc24b8b70d0f89791,
-        0xc76c51a30654be30,
-        0xd192e819d6ef5218,
-        0xd69906245565a910,
-        0xf40e35855771202a,
-        0x106aa07032bbd1b8,
-        0x19a4c116b8d2d0c8,
-        0x1e376c085141ab53,
-        0x2748774cdf8eeb99,
-        0x34b0bcb5e19b48a8,
-        0x391c0cb3c5c95a63,
-        0x4ed8aa4ae3418acb,
-        0x5b9cca4f7763e373,
-        0x682e6ff3d6b2b8a3,
-        0x748f82ee5defb2fc,
-        0x78a5636f43172f60,
-        0x84c87814a1f0ab72,
-        0x8cc702081a6439ec,
-        0x90befffa23631e28,
-        0xa4506cebde82bde9,
-        0xbef9a3f7b2c67915,
-        0xc67178f2e372532b,
-        0xca273eceea26619c,
-        0xd186b8c721c0c207,
-        0xeada7dd6cde0eb1e,
-        0xf57d4f7fee6ed178,
-        0x06f067aa72176fba,
-        0x0a637dc5a2c898a6,
-        0x113f9804bef90dae,
-        0x1b710b35131c471b,
-        0x28db77f523047d84,
-        0x32caab7b40c72493,
-        0x3c9ebe0a15c9bebc,
-        0x431d67c49c100d4c,
-        0x4cc5d4becb3e42b6,
-        0x597f299cfc657e2a,
-        0x5fcb6fab3ad6faec,
-        0x6c44198c4a475817,
+impl KnownAnswer for Sha224 {
+    const KAT_OUTPUT: &'static [u8] = &[
+        0x23, 0x09, 0x7d, 0x22, 0x34, 0x05, 0xd8, 0x22, 0x86, 0x42, 0xa4, 0x77, 0xbd, 0xa2, 0x55,
+        0xb3, 0x2a, 0xad, 0xbc, 0xe4, 0xbd, 0xa0, 0xb3, 0xf7, 0xe3, 0x6c, 0x9d, 0xa7,
     ];
-    let block = bytemuck::cast_slice::<u8, [u8; 8]>(block);
-    let mut w = [0u64; 80];
-    for i in 0..16 {
-        w[i] = u64::from_be_bytes(block[i]);
-    }
+}
 
-    for i in 16..80 {
-        let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
-        let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
-        w[i] = w[i - 16]
-            .wrapping_add(s0)
-            .wrapping_add(w[i - 7])
-            .wrapping_add(s1);
-    }
+impl KnownAnswer for Sha256 {
+    const KAT_OUTPUT: &'static [u8] = &[
+        0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22,
+        0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00,
+        0x15, 0xad,
+    ];
+}
+
+const K64: [u64; 80] = [
+    0x428a2f98d728ae22,
+    0x7137449123ef65cd,
+    0xb5c0fbcfec4d3b2f,
+    0xe9b5dba58189dbbc,
+    0x3956c25bf348b538,
+    0x59f111f1b605d019,
+    0x923f82a4af194f9b,
+    0xab1c5ed5da6d8118,
+    0xd807aa98a3030242,
+    0x12835b0145706fbe,
+    0x243185be4ee4b28c,
+    0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f,
+    0x80deb1fe3b1696b1,
+    0x9bdc06a725c71235,
+    0xc19bf174cf692694,
+    0xe49b69c19ef14ad2,
+    0xefbe4786384f25e3,
+    0x0fc19dc68b8cd5b5,
+    0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275,
+    0x4a7484aa6ea6e483,
+    0x5cb0a9dcbd41fbd4,
+    0x76f988da831153b5,
+    0x983e5152ee66dfab,
+    0xa831c66d2db43210,
+    0xb00327c898fb213f,
+    0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2,
+    0xd5a79147930aa725,
+    0x06ca6351e003826f,
+    0x142929670a0e6e70,
+    0x27b70a8546d22ffc,
+    0x2e1b21385c26c926,
+    0x4d2c6dfc5ac42aed,
+    0x53380d139d95b3df,
+    0x650a73548baf63de,
+    0x766a0abb3c77b2a8,
+    0x81c2c92e47edaee6,
+    0x92722c851482353b,
+    0xa2bfe8a14cf10364,
+    0xa81a664bbc423001,
+    0xc24b8b70d0f89791,
+    0xc76c51a30654be30,
+    0xd192e819d6ef5218,
+    0xd69906245565a910,
+    0xf40e35855771202a,
+    0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8,
+    0x1e376c085141ab53,
+    0x2748774cdf8eeb99,
+    0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63,
+    0x4ed8aa4ae3418acb,
+    0x5b9cca4f7763e373,
+    0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc,
+    0x78a5636f43172f60,
+    0x84c87814a1f0ab72,
+    0x8cc702081a6439ec,
+    0x90befffa23631e28,
+    0xa4506cebde82bde9,
+    0xbef9a3f7b2c67915,
+    0xc67178f2e372532b,
+    0xca273eceea26619c,
+    0xd186b8c721c0c207,
+    0xeada7dd6cde0eb1e,
+    0xf57d4f7fee6ed178,
+    0x06f067aa72176fba,
+    0x0a637dc5a2c898a6,
+    0x113f9804bef90dae,
+    0x1b710b35131c471b,
+    0x28db77f523047d84,
+    0x32caab7b40c72493,
+    0x3c9ebe0a15c9bebc,
+    0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6,
+    0x597f299cfc657e2a,
+    0x5fcb6fab3ad6faec,
+    0x6c44198c4a475817,
+];
+
+const fn sha64_compress(w: &[u64; 16], h: &[u64; 8]) -> [u64; 8] {
+    let w = {
+        let mut ret = [0u64; 80];
+        let mut i = 0;
+        while i < 16 {
+            ret[i] = w[i];
+            i += 1;
+        }
+        while i < 80 {
+            let s0 = ret[i - 15].rotate_right(1) ^ ret[i - 15].rotate_right(8) ^ (ret[i - 15] >> 7);
+            let s1 = ret[i - 2].rotate_right(19) ^ ret[i - 2].rotate_right(61) ^ (ret[i - 2] >> 6);
+            ret[i] = ret[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(ret[i - 7])
+                .wrapping_add(s1);
+            i += 1;
+        }
+        ret
+    };
 
     let mut a = h[0];
     let mut b = h[1];
@@ -275,13 +328,14 @@ fn do_sha64_block(block: &[u8], h: &mut [u64; 8]) {
     let mut g = h[6];
     let mut l = h[7];
 
-    for i in 0..80 {
+    let mut i = 0;
+    while i < 80 {
         let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
         let ch = (e & f) ^ ((!e) & g);
         let temp1 = l
             .wrapping_add(s1)
             .wrapping_add(ch)
-            .wrapping_add(k[i])
+            .wrapping_add(K64[i])
             .wrapping_add(w[i]);
         let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
         let maj = (a & b) ^ (a & c) ^ (b & c);
@@ -295,18 +349,134 @@ fn do_sha64_block(block: &[u8], h: &mut [u64; 8]) {
         c = b;
         b = a;
         a = temp1.wrapping_add(temp2);
+        i += 1;
     }
 
-    h[0] = h[0].wrapping_add(a);
-    h[1] = h[1].wrapping_add(b);
-    h[2] = h[2].wrapping_add(c);
-    h[3] = h[3].wrapping_add(d);
-    h[4] = h[4].wrapping_add(e);
-    h[5] = h[5].wrapping_add(f);
-    h[6] = h[6].wrapping_add(g);
-    h[7] = h[7].wrapping_add(l);
+    [
+        h[0].wrapping_add(a),
+        h[1].wrapping_add(b),
+        h[2].wrapping_add(c),
+        h[3].wrapping_add(d),
+        h[4].wrapping_add(e),
+        h[5].wrapping_add(f),
+        h[6].wrapping_add(g),
+        h[7].wrapping_add(l),
+    ]
+}
+
+fn do_sha64_block(block: &[u8], h: &mut [u64; 8]) {
+    let block = bytemuck::cast_slice::<u8, [u8; 8]>(block);
+    let mut w = [0u64; 16];
+    for (w, b) in w.iter_mut().zip(block) {
+        *w = u64::from_be_bytes(*b);
+    }
+    *h = sha64_compress(&w, h);
+    w.zeroize();
+}
+
+const SHA512_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const SHA384_IV: [u64; 8] = [
+    0xcbbb9d5dc1059ed8,
+    0x629a292a367cd507,
+    0x9159015a3070dd17,
+    0x152fecd8f70e5939,
+    0x67332667ffc00b31,
+    0x8eb44a8768581511,
+    0xdb0c2e0d64f98fa7,
+    0x47b5481dbefa4fa4,
+];
+
+const SHA512_224_IV: [u64; 8] = [
+    0x8c3d37c819544da2,
+    0x73e1996689dcd4d6,
+    0x1dfab7ae32ff9c82,
+    0x679dd514582f9fcf,
+    0x0f6d2b697bd44da8,
+    0x77e36f7304c48942,
+    0x3f9d85a86a1d36c8,
+    0x1112e6ad91d692a1,
+];
+
+const SHA512_256_IV: [u64; 8] = [
+    0x22312194fc2bf72c,
+    0x9f555fa3c84c64c2,
+    0x2393b86b6f53b151,
+    0x963877195940eabd,
+    0x96283ee2a88effe3,
+    0xbe5e1e2553863992,
+    0x2b0199fc2c85b8aa,
+    0x0eb72ddc81c52ca2,
+];
+
+///
+/// Computes the SHA-512/t initial hash value of FIPS 180-4 section 5.3.6, which is SHA-512 of the string `"SHA-512/t"`,
+///  with the SHA-512 IV xored with `a5a5...`
+const fn sha512_t_iv(t: usize) -> [u64; 8] {
+    assert!(t > 0 && t < 512 && t != 384 && t % 8 == 0);
+    let mut h = SHA512_IV;
+    let mut i = 0;
+    while i < 8 {
+        h[i] ^= 0xa5a5a5a5a5a5a5a5;
+        i += 1;
+    }
+
+    let mut msg = [0u8; 128];
+    let prefix = b"SHA-512/";
+    let mut len = 0;
+    while len < prefix.len() {
+        msg[len] = prefix[len];
+        len += 1;
+    }
+    let mut div = 100;
+    while div > t {
+        div /= 10;
+    }
+    while div > 0 {
+        msg[len] = b'0' + ((t / div) % 10) as u8;
+        len += 1;
+        div /= 10;
+    }
+    msg[len] = 0x80;
+    msg[127] = (len * 8) as u8;
+
+    let mut w = [0u64; 16];
+    let mut i = 0;
+    while i < 16 {
+        let mut j = 0;
+        while j < 8 {
+            w[i] = (w[i] << 8) | msg[8 * i + j] as u64;
+            j += 1;
+        }
+        i += 1;
+    }
+    sha64_compress(&w, &h)
 }
 
+const fn iv_eq(a: &[u64; 8], b: &[u64; 8]) -> bool {
+    let mut i = 0;
+    while i < 8 {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const _: () = assert!(iv_eq(&SHA512_224_IV, &sha512_t_iv(224)));
+const _: () = assert!(iv_eq(&SHA512_256_IV, &sha512_t_iv(256)));
+
+#[derive(Clone)]
 pub struct Sha64<const BITS: usize> {
     h: [u64; 8],
     size: u128,
@@ -315,16 +485,7 @@ pub struct Sha64<const BITS: usize> {
 impl Sha64<384> {
     pub const fn new() -> Self {
         Self {
-            h: [
-                0xcbbb9d5dc1059ed8,
-                0x629a292a367cd507,
-                0x9159015a3070dd17,
-                0x152fecd8f70e5939,
-                0x67332667ffc00b31,
-                0x8eb44a8768581511,
-                0xdb0c2e0d64f98fa7,
-                0x47b5481dbefa4fa4,
-            ],
+            h: SHA384_IV,
             size: 0,
         }
     }
@@ -333,16 +494,7 @@ impl Sha64<384> {
 impl Sha64<512> {
     pub const fn new() -> Self {
         Self {
-            h: [
-                0x6a09e667f3bcc908,
-                0xbb67ae8584caa73b,
-                0x3c6ef372fe94f82b,
-                0xa54ff53a5f1d36f1,
-                0x510e527fade682d1,
-                0x9b05688c2b3e6c1f,
-                0x1f83d9abfb41bd6b,
-                0x5be0cd19137e2179,
-            ],
+            h: SHA512_IV,
             size: 0,
         }
     }
@@ -351,16 +503,7 @@ impl Sha64<512> {
 impl Sha64<224> {
     pub const fn new() -> Self {
         Self {
-            h: [
-                0x8C3D37C819544DA2,
-                0x73E1996689DCD4D6,
-                0x1DFAB7AE32FF9C82,
-                0x679DD514582F9FCF,
-                0x0F6D2B697BD44DA8,
-                0x77E36F7304C48942,
-                0x3F9D85A86A1D36C8,
-                0x1112E6AD91D692A1,
-            ],
+            h: SHA512_224_IV,
             size: 0,
         }
     }
@@ -369,16 +512,7 @@ impl Sha64<224> {
 impl Sha64<256> {
     pub const fn new() -> Self {
         Self {
-            h: [
-                0x22312194FC2BF72C,
-                0x9F555FA3C84C64C2,
-                0x2393B86B6F53B151,
-                0x963877195940EABD,
-                0x96283EE2A88EFFE3,
-                0xBE5E1E2553863992,
-                0x2B0199FC2C85B8AA,
-                0x0EB72DDC81C52CA2,
-            ],
+            h: SHA512_256_IV,
             size: 0,
         }
     }
@@ -389,25 +523,50 @@ impl<const BITS: usize> Sha64<BITS> {
         Self { h, size: 0 }
     }
 
-    pub fn generate_iv() -> Self {
-        assert!(BITS < 512);
-        assert_ne!(BITS, 384);
-        let mut modified = {
-            let mut sha512 = Sha64::<512>::new();
-            for h in &mut sha512.h {
-                *h ^= 0xa5a5a5a5a5a5a5a5;
-            }
-            sha512
-        };
-        let msg = format!("SHA-512/{}", BITS);
-        let mut out = [0; 64];
-        modified.do_final(msg.as_bytes(), &mut out);
-        let mut h = [0; 8];
-        for (i, v) in h.iter_mut().zip(bytemuck::cast_slice::<u8, [u8; 8]>(&out)) {
-            *i = u64::from_be_bytes(*v);
+    ///
+    /// Computes the SHA-512/t initial hash value for `t = BITS`, as specified by FIPS 180-4.
+    ///
+    /// This can be evaluated at compile time, and supports truncated variants other than SHA-512/224 and SHA-512/256.
+    pub const fn generate_iv() -> Self {
+        Self::with_iv(sha512_t_iv(BITS))
+    }
+}
+
+impl<const BITS: usize> Sha64<BITS> {
+    ///
+    /// Exports the state after the blocks absorbed so far, as the 8 hash words followed by the 128-bit message length in bits, all big-endian.
+    ///
+    /// As with [`Sha32::export_state`], the state must be protected like the data that was hashed.
+    pub fn export_state(&self) -> Zeroizing<[u8; 80]> {
+        let mut out = Zeroizing::new([0u8; 80]);
+        for (o, h) in out.chunks_mut(8).zip(self.h) {
+            o.copy_from_slice(&h.to_be_bytes());
         }
+        out[64..].copy_from_slice(&self.size.to_be_bytes());
+        out
+    }
 
-        Self::with_iv(h)
+    ///
+    /// Restores a state produced by [`Sha64::export_state`], to continue hashing from the next block.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidData`] if the length is not a whole number of blocks.
+    pub fn import_state(state: &[u8; 80]) -> Result<Self> {
+        let mut size = [0u8; 16];
+        size.copy_from_slice(&state[64..]);
+        let size = u128::from_be_bytes(size);
+        if size % 1024 != 0 {
+            return Err(Error::new_with_message(
+                ErrorKind::InvalidData,
+                "SHA-512 state length is not a whole number of blocks",
+            ));
+        }
+        let mut h = [0u64; 8];
+        for (h, b) in h.iter_mut().zip(state.chunks(8)) {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(b);
+            *h = u64::from_be_bytes(word);
+        }
+        Ok(Self { h, size })
     }
 }
 
@@ -442,52 +601,16 @@ impl<const BITS: usize> Digest for Sha64<BITS> {
     fn init(&mut self) {
         self.size = 0;
         self.h = match BITS {
-            224 => [
-                0x8C3D37C819544DA2,
-                0x73E1996689DCD4D6,
-                0x1DFAB7AE32FF9C82,
-                0x679DD514582F9FCF,
-                0x0F6D2B697BD44DA8,
-                0x77E36F7304C48942,
-                0x3F9D85A86A1D36C8,
-                0x1112E6AD91D692A1,
-            ],
-            256 => [
-                0x22312194FC2BF72C,
-                0x9F555FA3C84C64C2,
-                0x2393B86B6F53B151,
-                0x963877195940EABD,
-                0x96283EE2A88EFFE3,
-                0xBE5E1E2553863992,
-                0x2B0199FC2C85B8AA,
-                0x0EB72DDC81C52CA2,
-            ],
-            384 => [
-                0xcbbb9d5dc1059ed8,
-                0x629a292a367cd507,
-                0x9159015a3070dd17,
-                0x152fecd8f70e5939,
-                0x67332667ffc00b31,
-                0x8eb44a8768581511,
-                0xdb0c2e0d64f98fa7,
-                0x47b5481dbefa4fa4,
-            ],
-            512 => [
-                0x6a09e667f3bcc908,
-                0xbb67ae8584caa73b,
-                0x3c6ef372fe94f82b,
-                0xa54ff53a5f1d36f1,
-                0x510e527fade682d1,
-                0x9b05688c2b3e6c1f,
-                0x1f83d9abfb41bd6b,
-                0x5be0cd19137e2179,
-            ],
-            _ => Self::generate_iv().h,
+            224 => SHA512_224_IV,
+            256 => SHA512_256_IV,
+            384 => SHA384_IV,
+            512 => SHA512_IV,
+            _ => sha512_t_iv(BITS),
         }
     }
 
     fn update(&mut self, block: &[u8]) {
-        self.size += BITS as u128;
+        self.size += 1024;
         do_sha64_block(block, &mut self.h);
     }
 
@@ -503,7 +626,7 @@ impl<const BITS: usize> Digest for Sha64<BITS> {
         self.size += (lblock.len() as u128) * 8;
         let ml = self.size;
         bytes[len] = 0x80;
-        if (Self::BLOCK_SIZE - len) < 16 {
+        if (Self::BLOCK_SIZE - len) < 17 {
             self.update(&bytes);
             bytes = [0u8; 128];
         }
@@ -527,3 +650,88 @@ pub type Sha384 = Sha64<384>;
 pub type Sha512 = Sha64<512>;
 pub type Sha512_224 = Sha64<224>;
 pub type Sha512_256 = Sha64<256>;
+
+impl KnownAnswer for Sha384 {
+    const KAT_OUTPUT: &'static [u8] = &[
+        0xcb, 0x00, 0x75, 0x3f, 0x45, 0xa3, 0x5e, 0x8b, 0xb5, 0xa0, 0x3d, 0x69, 0x9a, 0xc6, 0x50,
+        0x07, 0x27, 0x2c, 0x32, 0xab, 0x0e, 0xde, 0xd1, 0x63, 0x1a, 0x8b, 0x60, 0x5a, 0x43, 0xff,
+        0x5b, 0xed, 0x80, 0x86, 0x07, 0x2b, 0xa1, 0xe7, 0xcc, 0x23, 0x58, 0xba, 0xec, 0xa1, 0x34,
+        0xc8, 0x25, 0xa7,
+    ];
+}
+
+impl KnownAnswer for Sha512 {
+    const KAT_OUTPUT: &'static [u8] = &[
+        0xdd, 0xaf, 0x35, 0xa1, 0x93, 0x61, 0x7a, 0xba, 0xcc, 0x41, 0x73, 0x49, 0xae, 0x20, 0x41,
+        0x31, 0x12, 0xe6, 0xfa, 0x4e, 0x89, 0xa9, 0x7e, 0xa2, 0x0a, 0x9e, 0xee, 0xe6, 0x4b, 0x55,
+        0xd3, 0x9a, 0x21, 0x92, 0x99, 0x2a, 0x27, 0x4f, 0xc1, 0xa8, 0x36, 0xba, 0x3c, 0x23, 0xa3,
+        0xfe, 0xeb, 0xbd, 0x45, 0x4d, 0x44, 0x23, 0x64, 0x3c, 0xe8, 0x0e, 0x2a, 0x9a, 0xc9, 0x4f,
+        0xa5, 0x4c, 0xa4, 0x9f,
+    ];
+}
+
+impl KnownAnswer for Sha512_224 {
+    const KAT_OUTPUT: &'static [u8] = &[
+        0x46, 0x34, 0x27, 0x0f, 0x70, 0x7b, 0x6a, 0x54, 0xda, 0xae, 0x75, 0x30, 0x46, 0x08, 0x42,
+        0xe2, 0x0e, 0x37, 0xed, 0x26, 0x5c, 0xee, 0xe9, 0xa4, 0x3e, 0x89, 0x24, 0xaa,
+    ];
+}
+
+impl KnownAnswer for Sha512_256 {
+    const KAT_OUTPUT: &'static [u8] = &[
+        0x53, 0x04, 0x8e, 0x26, 0x81, 0x94, 0x1e, 0xf9, 0x9b, 0x2e, 0x29, 0xb7, 0x6b, 0x4c, 0x7d,
+        0xab, 0xe4, 0xc2, 0xd0, 0xc6, 0x34, 0xfc, 0x6d, 0x46, 0xe0, 0xe2, 0xf1, 0x31, 0x07, 0xe7,
+        0xaf, 0x23,
+    ];
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn sha512_t_iv_matches_fips() {
+        assert_eq!(super::sha512_t_iv(224), super::SHA512_224_IV);
+        assert_eq!(super::sha512_t_iv(256), super::SHA512_256_IV);
+        assert_eq!(super::Sha64::<256>::generate_iv().h, super::SHA512_256_IV);
+    }
+
+    #[test]
+    fn sha2_export_import_round_trip() {
+        use crate::digest::{Digest, KnownAnswer};
+        use crate::error::ErrorKind;
+
+        let msg = [0x61u8; 200];
+        let mut expected = [0u8; 32];
+        crate::digest::digest(super::Sha256::new(), &msg, &mut expected);
+        let mut first = super::Sha256::new();
+        first.update(&msg[..64]);
+        first.update(&msg[64..128]);
+        let state = first.export_state();
+        let mut resumed = super::Sha256::import_state(&state).unwrap();
+        resumed.update(&msg[128..192]);
+        let mut out = [0u8; 32];
+        resumed.do_final(&msg[192..], &mut out);
+        assert_eq!(out, expected);
+
+        let mut expected = [0u8; 64];
+        crate::digest::digest(super::Sha512::new(), &msg, &mut expected);
+        let mut first = super::Sha512::new();
+        first.update(&msg[..128]);
+        let state = first.export_state();
+        let mut resumed = super::Sha512::import_state(&state).unwrap();
+        let mut out = [0u8; 64];
+        resumed.do_final(&msg[128..], &mut out);
+        assert_eq!(out, expected);
+
+        let mut state = *super::Sha256::new().export_state();
+        let mut out = [0u8; 32];
+        super::Sha256::import_state(&state)
+            .unwrap()
+            .do_final(b"abc", &mut out);
+        assert_eq!(out, super::Sha256::KAT_OUTPUT);
+        state[39] = 8;
+        assert!(matches!(
+            super::Sha256::import_state(&state),
+            Err(e) if e.kind() == ErrorKind::InvalidData
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+//!
+//! The encoding primitives of NIST SP 800-185 section 2.3, shared by cSHAKE, KMAC, TupleHash and ParallelHash.
+
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+///
+/// An integer encoded by [`left_encode`] or [`right_encode`]: at most 8 bytes of value and 1 byte of length
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Encoding {
+    buf: [u8; 9],
+    len: u8,
+}
+
+impl Deref for Encoding {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.buf[..usize::from(self.len)]
+    }
+}
+
+impl AsRef<[u8]> for Encoding {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+///
+/// The minimal big-endian bytes of `x` (at least one byte), right-aligned in an 8-byte array, and their count
+fn be_bytes(x: u64) -> ([u8; 8], usize) {
+    let n = (8 - x.leading_zeros() as usize / 8).max(1);
+    (x.to_be_bytes(), n)
+}
+
+///
+/// Encodes `x` as the number of bytes in its big-endian form, followed by those bytes
+pub fn left_encode(x: u64) -> Encoding {
+    let (bytes, n) = be_bytes(x);
+    let mut buf = [0u8; 9];
+    buf[0] = n as u8;
+    buf[1..=n].copy_from_slice(&bytes[8 - n..]);
+    Encoding {
+        buf,
+        len: n as u8 + 1,
+    }
+}
+
+///
+/// Encodes `x` as its big-endian bytes, followed by the number of those bytes
+pub fn right_encode(x: u64) -> Encoding {
+    let (bytes, n) = be_bytes(x);
+    let mut buf = [0u8; 9];
+    buf[..n].copy_from_slice(&bytes[8 - n..]);
+    buf[n] = n as u8;
+    Encoding {
+        buf,
+        len: n as u8 + 1,
+    }
+}
+
+///
+/// Encodes the bit string `s` as `left_encode(len(s))` (the length in bits) followed by `s`
+pub fn encode_string(s: &[u8]) -> Vec<u8> {
+    let prefix = left_encode(s.len() as u64 * 8);
+    let mut out = Vec::with_capacity(prefix.len() + s.len());
+    out.extend_from_slice(&prefix);
+    out.extend_from_slice(s);
+    out
+}
+
+///
+/// Prepends `left_encode(w)` to `x` and pads the result with zero bytes to a multiple of `w` bytes.
+///
+/// Panics if `w` is zero.
+pub fn bytepad(x: &[u8], w: usize) -> Vec<u8> {
+    assert!(w > 0, "bytepad width must be nonzero");
+    let prefix = left_encode(w as u64);
+    let mut out = Vec::with_capacity((prefix.len() + x.len()).next_multiple_of(w));
+    out.extend_from_slice(&prefix);
+    out.extend_from_slice(x);
+    out.resize(out.len().next_multiple_of(w), 0);
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bytepad, encode_string, left_encode, right_encode};
+
+    #[test]
+    pub fn sp800_185_integer_encodings() {
+        assert_eq!(*left_encode(0), [1, 0]);
+        assert_eq!(*right_encode(0), [0, 1]);
+        assert_eq!(*left_encode(255), [1, 255]);
+        assert_eq!(*right_encode(255), [255, 1]);
+        assert_eq!(*left_encode(256), [2, 1, 0]);
+        assert_eq!(*right_encode(256), [1, 0, 2]);
+        assert_eq!(*left_encode(168), [1, 168]);
+        assert_eq!(*left_encode(0x0102030405), [5, 1, 2, 3, 4, 5]);
+        assert_eq!(
+            *right_encode(u64::MAX),
+            [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 8]
+        );
+    }
+
+    #[test]
+    pub fn sp800_185_encode_string_bytepad() {
+        assert_eq!(encode_string(b""), [1, 0]);
+        // The function name string used by KMAC
+        assert_eq!(encode_string(b"KMAC"), [1, 32, b'K', b'M', b'A', b'C']);
+        assert_eq!(encode_string(&[0xaa; 32])[..3], [2, 1, 0]);
+
+        let mut x = encode_string(b"KMAC");
+        x.extend_from_slice(&encode_string(b""));
+        let padded = bytepad(&x, 168);
+        assert_eq!(padded.len(), 168);
+        assert_eq!(padded[..10], [1, 168, 1, 32, b'K', b'M', b'A', b'C', 1, 0]);
+        assert!(padded[10..].iter().all(|&b| b == 0));
+        assert_eq!(bytepad(&[0; 6], 8).len(), 8);
+        assert_eq!(bytepad(&[0; 7], 8).len(), 16);
+    }
+}
@@ -0,0 +1,247 @@
+//!
+//! Helpers shared by elliptic-curve routines.
+
+use alloc::{vec, vec::Vec};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{
+    digest::{digest, Digest},
+    error::{Error, ErrorKind, Result},
+    secret::SecretUint,
+};
+
+///
+/// Clamps an X25519 secret scalar in place, as in RFC 7748 section 5.
+///
+/// The low 3 bits are cleared (so the scalar is a multiple of the cofactor 8), bit 255 is cleared, and bit 254 is set.
+/// Only fixed masks are applied, so the timing is independent of the scalar.
+pub fn clamp_scalar_x25519(scalar: &mut [u8; 32]) {
+    scalar[0] &= 0xf8;
+    scalar[31] &= 0x7f;
+    scalar[31] |= 0x40;
+}
+
+///
+/// Clamps the scalar half of an expanded Ed25519 secret key in place, as in RFC 8032 section 5.1.5.
+///
+/// `expanded` is the SHA-512 hash of the 32-byte secret key. Its first 32 bytes are clamped exactly as by [`clamp_scalar_x25519`],
+///  and the second 32 bytes (the nonce prefix) are left unchanged.
+pub fn clamp_scalar_ed25519(expanded: &mut [u8; 64]) {
+    let mut scalar = [0u8; 32];
+    scalar.copy_from_slice(&expanded[..32]);
+    clamp_scalar_x25519(&mut scalar);
+    expanded[..32].copy_from_slice(&scalar);
+    scalar.zeroize();
+}
+
+///
+/// The `expand_message_xmd` function of RFC 9380 section 5.3.1, producing `len` pseudorandom bytes from `msg` under the domain separation tag `dst`.
+///
+/// Returns an error of kind [`ErrorKind::InvalidInput`] if `dst` is longer than 255 bytes, or `len` needs more than 255 digest outputs or exceeds 65535.
+pub fn expand_message_xmd<D: Digest + Default>(
+    msg: &[u8],
+    dst: &[u8],
+    len: usize,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let ell = len.div_ceil(D::OUTPUT_SIZE);
+    if ell > 255 || len > 65535 || dst.len() > 255 {
+        return Err(Error::new_with_message(
+            ErrorKind::InvalidInput,
+            "expand_message_xmd length or DST too long",
+        ));
+    }
+    let mut dst_prime = Vec::with_capacity(dst.len() + 1);
+    dst_prime.extend_from_slice(dst);
+    dst_prime.push(dst.len() as u8);
+
+    let mut input = Zeroizing::new(vec![0u8; D::BLOCK_SIZE]);
+    input.extend_from_slice(msg);
+    input.extend_from_slice(&(len as u16).to_be_bytes());
+    input.push(0);
+    input.extend_from_slice(&dst_prime);
+    let mut b0 = Zeroizing::new(vec![0u8; D::OUTPUT_SIZE]);
+    digest(D::default(), &input, &mut b0);
+
+    let mut out = Zeroizing::new(Vec::with_capacity(ell * D::OUTPUT_SIZE));
+    let mut bi = Zeroizing::new(vec![0u8; D::OUTPUT_SIZE]);
+    for i in 1..=ell {
+        // b_i = H(strxor(b_0, b_(i-1)) || i || DST_prime), where b_(i-1) is empty for i=1
+        input.clear();
+        input.extend_from_slice(&b0);
+        if i > 1 {
+            for (a, b) in input.iter_mut().zip(bi.iter()) {
+                *a ^= b;
+            }
+        }
+        input.push(i as u8);
+        input.extend_from_slice(&dst_prime);
+        digest(D::default(), &input, &mut bi);
+        out.extend_from_slice(&bi);
+    }
+    out.truncate(len);
+    Ok(out)
+}
+
+///
+/// The `hash_to_field` function of RFC 9380 section 5.2 for a prime field, hashing `msg` to `count` elements of `[0, modulus)` with [`expand_message_xmd`].
+///
+/// Each element is reduced from `l` bytes of output, where RFC 9380 specifies `l = ceil((ceil(log2(modulus)) + k) / 8)` for security level `k`
+///  (48 for P-256 with `k=128`). The reduction is a constant-time double-and-add over the bits, so neither the output nor the time reveals the hashed bytes.
+///
+/// Returns an error of kind [`ErrorKind::InvalidInput`] if `l` is zero, or under the same conditions as [`expand_message_xmd`].
+/// Panics if `N` is zero or `modulus<=1`.
+pub fn hash_to_field<D: Digest + Default, const N: usize>(
+    msg: &[u8],
+    dst: &[u8],
+    modulus: &SecretUint<N>,
+    count: usize,
+    l: usize,
+) -> Result<Vec<SecretUint<N>>> {
+    assert!(N > 0, "modulus must have at least one byte");
+    if l == 0 {
+        return Err(Error::new_with_message(
+            ErrorKind::InvalidInput,
+            "hash_to_field needs at least one byte per element",
+        ));
+    }
+    let mut one = [0u8; N];
+    one[N - 1] = 1;
+    let one = SecretUint::from_be_bytes(one);
+    assert!(one.ct_lt(modulus), "modulus must be greater than 1");
+    let len = count.checked_mul(l).ok_or_else(|| {
+        Error::new_with_message(ErrorKind::InvalidInput, "hash_to_field output too long")
+    })?;
+    let uniform = expand_message_xmd::<D>(msg, dst, len)?;
+    Ok(uniform
+        .chunks(l)
+        .map(|bytes| {
+            let mut acc = SecretUint::zero();
+            for byte in bytes {
+                for bit in (0..8).rev() {
+                    acc = acc.add_mod(&acc, modulus);
+                    let added = acc.add_mod(&one, modulus);
+                    acc.conditional_assign(&added, (byte >> bit) & 1 != 0);
+                }
+            }
+            acc
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{clamp_scalar_ed25519, clamp_scalar_x25519, expand_message_xmd, hash_to_field};
+    use crate::{digest::sha2::Sha256, error::ErrorKind, secret::SecretUint};
+
+    fn unhex<const N: usize>(s: &str) -> [u8; N] {
+        let mut out = [0u8; N];
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+
+    fn check_clamped(s: &[u8]) {
+        assert_eq!(s[0] & 0x07, 0);
+        assert_eq!(s[31] & 0x80, 0);
+        assert_eq!(s[31] & 0x40, 0x40);
+    }
+
+    #[test]
+    pub fn clamp_x25519_bits() {
+        for fill in [0x00, 0xff, 0x5a, 0xa5] {
+            let mut s = [fill; 32];
+            clamp_scalar_x25519(&mut s);
+            check_clamped(&s);
+            // No other bits are changed
+            assert!(s[1..31].iter().all(|&b| b == fill));
+            assert_eq!(s[0], fill & 0xf8);
+            assert_eq!(s[31], (fill & 0x3f) | 0x40);
+        }
+    }
+
+    #[test]
+    pub fn clamp_x25519_rfc7748() {
+        // The first scalar of RFC 7748 section 5.2, which decodes to the given little-endian value once clamped
+        let mut s = [
+            0xa5, 0x46, 0xe3, 0x6b, 0xf0, 0x52, 0x7c, 0x9d, 0x3b, 0x16, 0x15, 0x4b, 0x82, 0x46,
+            0x5e, 0xdd, 0x62, 0x14, 0x4c, 0x0a, 0xc1, 0xfc, 0x5a, 0x18, 0x50, 0x6a, 0x22, 0x44,
+            0xba, 0x44, 0x9a, 0xc4,
+        ];
+        clamp_scalar_x25519(&mut s);
+        assert_eq!(s[0], 0xa0);
+        assert_eq!(s[31], 0x44);
+        check_clamped(&s);
+    }
+
+    #[test]
+    pub fn clamp_ed25519_only_scalar_half() {
+        let mut h = [0xffu8; 64];
+        clamp_scalar_ed25519(&mut h);
+        check_clamped(&h[..32]);
+        assert_eq!(h[32..], [0xffu8; 32]);
+    }
+
+    #[test]
+    pub fn expand_message_xmd_rfc9380() {
+        // RFC 9380 appendix K.1
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        assert_eq!(
+            expand_message_xmd::<Sha256>(b"", dst, 0x20).unwrap()[..],
+            unhex::<32>("68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235")
+        );
+        assert_eq!(
+            expand_message_xmd::<Sha256>(b"abc", dst, 0x20).unwrap()[..],
+            unhex::<32>("d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615")
+        );
+        assert_eq!(
+            expand_message_xmd::<Sha256>(b"", dst, 0x80).unwrap()[..],
+            unhex::<128>(
+                "af84c27ccfd45d41914fdff5df25293e221afc53d8ad2ac06d5e3e29485dadbe\
+                 e0d121587713a3e0dd4d5e69e93eb7cd4f5df4cd103e188cf60cb02edc3edf18\
+                 eda8576c412b18ffb658e3dd6ec849469b979d444cf7b26911a08e63cf31f9dc\
+                 c541708d3491184472c2c29bb749d4286b004ceb5ee6b9a7fa5b646c993f0ced"
+            )
+        );
+        assert!(expand_message_xmd::<Sha256>(b"", dst, 255 * 32 + 1).is_err());
+        assert!(expand_message_xmd::<Sha256>(b"", &[0; 256], 32).is_err());
+    }
+
+    #[test]
+    pub fn hash_to_field_p256_rfc9380() {
+        // RFC 9380 appendix J.1.1, P256_XMD:SHA-256_SSWU_RO_
+        let p = SecretUint::from_be_bytes(unhex::<32>(
+            "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff",
+        ));
+        let dst = b"QUUX-V01-CS02-with-P256_XMD:SHA-256_SSWU_RO_";
+        for (msg, u0, u1) in [
+            (
+                &b""[..],
+                "ad5342c66a6dd0ff080df1da0ea1c04b96e0330dd89406465eeba11582515009",
+                "8c0f1d43204bd6f6ea70ae8013070a1518b43873bcd850aafa0a9e220e2eea5a",
+            ),
+            (
+                &b"abc"[..],
+                "afe47f2ea2b10465cc26ac403194dfb68b7f5ee865cda61e9f3e07a537220af1",
+                "379a27833b0bfe6f7bdca08e1e83c760bf9a338ab335542704edcd69ce9e46e0",
+            ),
+        ] {
+            let u = hash_to_field::<Sha256, 32>(msg, dst, &p, 2, 48).unwrap();
+            assert_eq!(u.len(), 2);
+            assert_eq!(*u[0].as_be_bytes(), unhex::<32>(u0));
+            assert_eq!(*u[1].as_be_bytes(), unhex::<32>(u1));
+        }
+        assert_eq!(
+            hash_to_field::<Sha256, 32>(b"", dst, &p, 2, 0)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn hash_to_field_empty_modulus() {
+        let _ = hash_to_field::<Sha256, 0>(b"", b"dst", &SecretUint::zero(), 1, 48);
+    }
+}
@@ -0,0 +1,132 @@
+use core::fmt;
+
+///
+/// The category of an [`Error`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A parameter was invalid, for example a buffer of the wrong length
+    InvalidInput,
+    /// A MAC or authentication tag did not verify
+    AuthenticationFailed,
+    /// No implementation of a requested algorithm is available
+    ProviderNotFound,
+    /// Encoded data was malformed, for example an invalid character in base32 text
+    InvalidData,
+    /// An operation was interrupted before it completed, and may be retried
+    Interrupted,
+    /// Any other error
+    Other,
+}
+
+impl ErrorKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::InvalidInput => "invalid input",
+            ErrorKind::AuthenticationFailed => "authentication failed",
+            ErrorKind::ProviderNotFound => "algorithm not found",
+            ErrorKind::InvalidData => "invalid data",
+            ErrorKind::Interrupted => "operation interrupted",
+            ErrorKind::Other => "other error",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug)]
+enum Repr {
+    Simple(ErrorKind),
+    Message(ErrorKind, &'static str),
+}
+
+///
+/// The error type for fallible operations in this crate.
+///
+/// Errors never carry secret data, so they may be freely logged or displayed.
+#[derive(Debug)]
+pub struct Error(Repr);
+
+impl Error {
+    pub const fn new_with_message(kind: ErrorKind, msg: &'static str) -> Self {
+        Self(Repr::Message(kind, msg))
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            Repr::Simple(kind) | Repr::Message(kind, _) => kind,
+        }
+    }
+
+    ///
+    /// Returns the message given to [`Error::new_with_message`], or `None` for an error created from an [`ErrorKind`] alone.
+    ///
+    /// The message is returned as the original `&'static str`, without copying or allocating.
+    pub fn message_static(&self) -> Option<&'static str> {
+        match self.0 {
+            Repr::Simple(_) => None,
+            Repr::Message(_, msg) => Some(msg),
+        }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self(Repr::Simple(kind))
+    }
+}
+
+impl From<core::array::TryFromSliceError> for Error {
+    fn from(_: core::array::TryFromSliceError) -> Self {
+        Self::new_with_message(ErrorKind::InvalidInput, "slice has the wrong length")
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Repr::Simple(kind) => kind.fmt(f),
+            Repr::Message(kind, msg) => write!(f, "{}: {}", kind, msg),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl std::error::Error for Error {}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[cfg(test)]
+mod test {
+    use core::convert::TryInto;
+
+    use super::{Error, ErrorKind, Result};
+
+    #[test]
+    pub fn error_message_static() {
+        static MSG: &str = "wrong key length";
+        let err = Error::new_with_message(ErrorKind::InvalidInput, MSG);
+        let msg = err.message_static().unwrap();
+        assert_eq!(msg.as_ptr(), MSG.as_ptr());
+        assert_eq!(msg.len(), MSG.len());
+        assert_eq!(Error::from(ErrorKind::Other).message_static(), None);
+    }
+
+    fn parse_block(bytes: &[u8]) -> Result<[u8; 16]> {
+        let block: [u8; 16] = bytes.try_into()?;
+        Ok(block)
+    }
+
+    #[test]
+    pub fn error_from_try_from_slice() {
+        assert_eq!(parse_block(&[7; 16]).unwrap(), [7; 16]);
+        assert_eq!(
+            parse_block(&[7; 15]).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+}
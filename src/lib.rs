@@ -2,8 +2,27 @@
 #![deny(unsafe_code)]
 
 extern crate alloc;
+#[cfg(any(test, feature = "std"))]
+#[doc(hidden)]
+pub extern crate std as __std;
 
+pub mod algorithms;
+pub mod asm;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod checksum;
 pub mod cmp;
+pub mod commit;
 pub mod digest;
+pub mod ecc;
+pub mod error;
+pub mod mac;
+pub mod mem;
+pub mod otp;
 pub mod rand;
+pub mod secret;
 pub mod symm;
+pub mod util;
+
+#[cfg(all(test, feature = "timing-tests"))]
+mod timing_test;
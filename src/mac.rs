@@ -0,0 +1,5 @@
+pub mod cmac;
+pub(crate) mod field_poly;
+pub mod poly1305;
+pub mod polyval;
+pub mod siphash;
@@ -0,0 +1,164 @@
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{
+    digest::Digest,
+    symm::{Operation, SymmetricCipher},
+};
+
+///
+/// Doubles `block` in GF(2^128), as used to derive the CMAC subkeys and in S2V
+pub(crate) fn dbl(block: &mut [u8; 16]) {
+    let carry = block[0] >> 7;
+    for i in 0..15 {
+        block[i] = (block[i] << 1) | (block[i + 1] >> 7);
+    }
+    // Reduce by x^128 + x^7 + x^2 + x + 1 without branching on the carry
+    block[15] = (block[15] << 1) ^ (carry.wrapping_neg() & 0x87);
+}
+
+///
+/// The CMAC message authentication code of RFC 4493 (also known as OMAC1), over a block cipher with 16-byte blocks.
+///
+/// With [`crate::symm::aes::Aes`], this is AES-CMAC.
+pub struct Cmac<C> {
+    cipher: C,
+    k1: Zeroizing<[u8; 16]>,
+    k2: Zeroizing<[u8; 16]>,
+    state: Zeroizing<[u8; 16]>,
+}
+
+impl<C: SymmetricCipher> Cmac<C> {
+    ///
+    /// Creates a CMAC under `key`, which must be `C::KEY_SIZE` bytes.
+    ///
+    /// Panics if `C` does not have a 16-byte block.
+    pub fn new(cipher: C, key: &[u8]) -> Self {
+        assert_eq!(C::BLOCK_SIZE, 16, "CMAC requires a 16-byte block cipher");
+        let mut ret = Self {
+            cipher,
+            k1: Zeroizing::new([0; 16]),
+            k2: Zeroizing::new([0; 16]),
+            state: Zeroizing::new([0; 16]),
+        };
+        ret.reset_into(key);
+        ret
+    }
+
+    ///
+    /// Replaces the key and restarts the MAC, as if `self` had been constructed by [`Cmac::new`]
+    pub fn reset_into(&mut self, key: &[u8]) {
+        self.cipher.init(key, Operation::Encrypt);
+        let zero = [0u8; 16];
+        self.cipher.update(&zero, &mut *self.k1);
+        dbl(&mut self.k1);
+        *self.k2 = *self.k1;
+        dbl(&mut self.k2);
+        self.state.zeroize();
+    }
+
+    fn encrypt_state(&mut self) {
+        let block = *self.state;
+        self.cipher.update(&block, &mut *self.state);
+    }
+}
+
+impl<C> Zeroize for Cmac<C> {
+    fn zeroize(&mut self) {
+        self.k1.zeroize();
+        self.k2.zeroize();
+        self.state.zeroize();
+    }
+}
+
+impl<C: SymmetricCipher> Digest for Cmac<C> {
+    const OUTPUT_SIZE: usize = 16;
+    const BLOCK_SIZE: usize = 16;
+
+    fn init(&mut self) {
+        self.state.zeroize();
+    }
+
+    fn update(&mut self, block: &[u8]) {
+        for (s, b) in self.state.iter_mut().zip(block) {
+            *s ^= b;
+        }
+        self.encrypt_state();
+    }
+
+    fn do_final(&mut self, lblock: &[u8], out: &mut [u8]) {
+        assert!(lblock.len() <= 16);
+        for (s, b) in self.state.iter_mut().zip(lblock) {
+            *s ^= b;
+        }
+        if lblock.len() == 16 {
+            for (s, k) in self.state.iter_mut().zip(self.k1.iter()) {
+                *s ^= k;
+            }
+        } else {
+            self.state[lblock.len()] ^= 0x80;
+            for (s, k) in self.state.iter_mut().zip(self.k2.iter()) {
+                *s ^= k;
+            }
+        }
+        self.encrypt_state();
+        out[..16].copy_from_slice(&*self.state);
+        self.state.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Cmac;
+    use crate::{digest::digest, symm::aes::Aes};
+
+    #[test]
+    pub fn cmac_aes128_rfc4493() {
+        let key = [
+            0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf,
+            0x4f, 0x3c,
+        ];
+        let msg = [
+            0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93,
+            0x17, 0x2a, 0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac,
+            0x45, 0xaf, 0x8e, 0x51, 0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb,
+            0xc1, 0x19, 0x1a, 0x0a, 0x52, 0xef, 0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17,
+            0xad, 0x2b, 0x41, 0x7b, 0xe6, 0x6c, 0x37, 0x10,
+        ];
+        let expected: [(usize, [u8; 16]); 4] = [
+            (
+                0,
+                [
+                    0xbb, 0x1d, 0x69, 0x29, 0xe9, 0x59, 0x37, 0x28, 0x7f, 0xa3, 0x7d, 0x12, 0x9b,
+                    0x75, 0x67, 0x46,
+                ],
+            ),
+            (
+                16,
+                [
+                    0x07, 0x0a, 0x16, 0xb4, 0x6b, 0x4d, 0x41, 0x44, 0xf7, 0x9b, 0xdd, 0x9d, 0xd0,
+                    0x4a, 0x28, 0x7c,
+                ],
+            ),
+            (
+                40,
+                [
+                    0xdf, 0xa6, 0x67, 0x47, 0xde, 0x9a, 0xe6, 0x30, 0x30, 0xca, 0x32, 0x61, 0x14,
+                    0x97, 0xc8, 0x27,
+                ],
+            ),
+            (
+                64,
+                [
+                    0x51, 0xf0, 0xbe, 0xbf, 0x7e, 0x3b, 0x9d, 0x92, 0xfc, 0x49, 0x74, 0x17, 0x79,
+                    0x36, 0x3c, 0xfe,
+                ],
+            ),
+        ];
+        let mut cmac = Cmac::new(Aes::<128>::const_new(), &key);
+        for (len, tag) in expected {
+            let mut out = [0u8; 16];
+            digest(&mut cmac, &msg[..len], &mut out);
+            assert_eq!(out, tag, "length {}", len);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//!
+//! Horner evaluation of the polynomials underlying Poly1305 and POLYVAL.
+//!
+//! Both authenticators treat the message as the coefficients of a polynomial, and evaluate it at the key with `acc = (acc + block) * r` for each block.
+//! Only the field arithmetic differs, so it is supplied by the caller and the block splitting and padding is shared.
+
+use core::convert::TryInto;
+
+use zeroize::Zeroizing;
+
+///
+/// Absorbs `data` into `acc` with one Horner step per `B`-byte block: `add(acc, block, len)` followed by `mul(acc)`.
+///
+/// A final partial block is zero-padded to `B` bytes, and `len` gives its length before padding (it is `B` for every full block), so that callers which pad differently,
+///  such as Poly1305, can adjust it. The padded copy is zeroed after use. The accumulator is borrowed from the caller, which is responsible for zeroing it.
+///
+/// Neither closure is given any information about `data` other than through the block, so the loop is constant-time if they are.
+pub(crate) fn horner<A, const B: usize>(
+    acc: &mut A,
+    data: &[u8],
+    mut add: impl FnMut(&mut A, &[u8; B], usize),
+    mut mul: impl FnMut(&mut A),
+) {
+    let mut chunks = data.chunks_exact(B);
+    for block in &mut chunks {
+        add(acc, block.try_into().unwrap(), B);
+        mul(acc);
+    }
+    let rem = chunks.remainder();
+    if !rem.is_empty() {
+        let mut pad = Zeroizing::new([0u8; B]);
+        pad[..rem.len()].copy_from_slice(rem);
+        add(acc, &pad, rem.len());
+        mul(acc);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::horner;
+    use crate::mac::polyval::polyval_dot;
+
+    #[test]
+    pub fn horner_prime_field() {
+        // Evaluate over GF(65521) with 2-byte big-endian coefficients, against direct evaluation
+        const P: u64 = 65521;
+        let r = 12345u64;
+        let data = [0x12, 0x34, 0x56, 0x78, 0x9a];
+        let mut acc = 0u64;
+        horner(
+            &mut acc,
+            &data,
+            |acc, block: &[u8; 2], _| *acc = (*acc + u64::from(u16::from_be_bytes(*block))) % P,
+            |acc| *acc = *acc * r % P,
+        );
+        let coeffs = [0x1234u64, 0x5678, 0x9a00];
+        let expected = coeffs
+            .iter()
+            .enumerate()
+            .map(|(i, c)| c * (0..3 - i).fold(1, |x, _| x * r % P) % P)
+            .fold(0, |a, b| (a + b) % P);
+        assert_eq!(acc, expected);
+    }
+
+    #[test]
+    pub fn horner_partial_len() {
+        let mut lens = [0usize; 3];
+        let mut n = 0;
+        horner(
+            &mut n,
+            &[0xff; 37],
+            |n, block: &[u8; 16], len| {
+                lens[*n] = len;
+                assert!(block[len..].iter().all(|&b| b == 0));
+            },
+            |n| *n += 1,
+        );
+        assert_eq!(lens, [16, 16, 5]);
+    }
+
+    #[test]
+    pub fn horner_polyval_rfc8452() {
+        // RFC 8452 appendix A, as computed by mac::polyval
+        let h = u128::from_le_bytes([
+            0x25, 0x62, 0x93, 0x47, 0x58, 0x92, 0x42, 0x76, 0x1d, 0x31, 0xf8, 0x26, 0xba, 0x4b,
+            0x75, 0x7b,
+        ]);
+        let x = [
+            0x4f, 0x4f, 0x95, 0x66, 0x8c, 0x83, 0xdf, 0xb6, 0x40, 0x17, 0x62, 0xbb, 0x2d, 0x01,
+            0xa2, 0x62, 0xd1, 0xa2, 0x4d, 0xdd, 0x27, 0x21, 0xd0, 0x06, 0xbb, 0xe4, 0x5f, 0x20,
+            0xd3, 0xc9, 0xf3, 0x62,
+        ];
+        let mut acc = 0u128;
+        horner(
+            &mut acc,
+            &x,
+            |acc, block: &[u8; 16], _| *acc ^= u128::from_le_bytes(*block),
+            |acc| *acc = polyval_dot(*acc, h),
+        );
+        assert_eq!(
+            acc.to_le_bytes(),
+            [
+                0xf7, 0xa3, 0xb4, 0x7b, 0x84, 0x61, 0x19, 0xfa, 0xe5, 0xb7, 0x86, 0x6c, 0xf5, 0xe5,
+                0xb7, 0x7e
+            ]
+        );
+    }
+}
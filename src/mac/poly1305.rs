@@ -0,0 +1,244 @@
+use zeroize::{Zeroize, Zeroizing};
+
+use super::field_poly::horner;
+use crate::digest::Digest;
+
+///
+/// The Poly1305 one-time authenticator, as specified in RFC 8439.
+///
+/// The 32-byte key consists of the clamped multiplier `r` followed by the final addend `s`.
+/// A key must never be used to authenticate more than one message, so Poly1305 is normally keyed from a stream cipher, as in [`crate::symm::chacha20poly1305`].
+///
+/// Arithmetic is done in 26-bit limbs, and no branches or memory accesses depend on the key or the message.
+pub struct Poly1305 {
+    key: Zeroizing<[u8; 32]>,
+    r: [u32; 5],
+    h: [u32; 5],
+    pad: [u32; 4],
+}
+
+fn le32(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+impl Poly1305 {
+    pub fn new(key: &[u8; 32]) -> Self {
+        let mut ret = Self {
+            key: Zeroizing::new(*key),
+            r: [0; 5],
+            h: [0; 5],
+            pad: [0; 4],
+        };
+        ret.init();
+        ret
+    }
+
+    ///
+    /// Replaces the key and restarts the hash, as if `self` had been constructed by [`Poly1305::new`]
+    pub fn reset_into(&mut self, key: &[u8; 32]) {
+        *self.key = *key;
+        self.init();
+    }
+
+    ///
+    /// Adds the 16-byte block `m` to the accumulator. A block of `len<16` bytes has a 1 byte appended in place of the `2^128` bit, as in RFC 8439
+    fn add(&mut self, m: &[u8; 16], len: usize) {
+        let mut m = Zeroizing::new(*m);
+        let mut hibit = 1 << 24;
+        if len < 16 {
+            m[len] = 1;
+            hibit = 0;
+        }
+        self.h[0] += le32(&m[0..]) & 0x3ffffff;
+        self.h[1] += (le32(&m[3..]) >> 2) & 0x3ffffff;
+        self.h[2] += (le32(&m[6..]) >> 4) & 0x3ffffff;
+        self.h[3] += (le32(&m[9..]) >> 6) & 0x3ffffff;
+        self.h[4] += (le32(&m[12..]) >> 8) | hibit;
+    }
+
+    ///
+    /// Multiplies the accumulator by `r`, partially reducing modulo `2^130-5`
+    fn mul_r(&mut self) {
+        let [r0, r1, r2, r3, r4] = self.r.map(u64::from);
+        let (s1, s2, s3, s4) = (r1 * 5, r2 * 5, r3 * 5, r4 * 5);
+        let [h0, h1, h2, h3, h4] = self.h.map(u64::from);
+
+        let d0 = h0 * r0 + h1 * s4 + h2 * s3 + h3 * s2 + h4 * s1;
+        let mut d1 = h0 * r1 + h1 * r0 + h2 * s4 + h3 * s3 + h4 * s2;
+        let mut d2 = h0 * r2 + h1 * r1 + h2 * r0 + h3 * s4 + h4 * s3;
+        let mut d3 = h0 * r3 + h1 * r2 + h2 * r1 + h3 * r0 + h4 * s4;
+        let mut d4 = h0 * r4 + h1 * r3 + h2 * r2 + h3 * r1 + h4 * r0;
+
+        d1 += d0 >> 26;
+        d2 += d1 >> 26;
+        d3 += d2 >> 26;
+        d4 += d3 >> 26;
+        let mut h0 = (d0 as u32 & 0x3ffffff) + (d4 >> 26) as u32 * 5;
+        let h1 = (d1 as u32 & 0x3ffffff) + (h0 >> 26);
+        h0 &= 0x3ffffff;
+        self.h = [
+            h0,
+            h1,
+            d2 as u32 & 0x3ffffff,
+            d3 as u32 & 0x3ffffff,
+            d4 as u32 & 0x3ffffff,
+        ];
+    }
+
+    fn finalize(&mut self, out: &mut [u8]) {
+        let [mut h0, mut h1, mut h2, mut h3, mut h4] = self.h;
+
+        // Fully carry h
+        h2 += h1 >> 26;
+        h1 &= 0x3ffffff;
+        h3 += h2 >> 26;
+        h2 &= 0x3ffffff;
+        h4 += h3 >> 26;
+        h3 &= 0x3ffffff;
+        h0 += (h4 >> 26) * 5;
+        h4 &= 0x3ffffff;
+        h1 += h0 >> 26;
+        h0 &= 0x3ffffff;
+
+        // Compute g = h + 5 - 2^130, and select it if it did not underflow
+        let mut g0 = h0 + 5;
+        let mut g1 = h1 + (g0 >> 26);
+        g0 &= 0x3ffffff;
+        let mut g2 = h2 + (g1 >> 26);
+        g1 &= 0x3ffffff;
+        let mut g3 = h3 + (g2 >> 26);
+        g2 &= 0x3ffffff;
+        let mut g4 = (h4 + (g3 >> 26)).wrapping_sub(1 << 26);
+        g3 &= 0x3ffffff;
+
+        let mask = (g4 >> 31).wrapping_sub(1);
+        g0 &= mask;
+        g1 &= mask;
+        g2 &= mask;
+        g3 &= mask;
+        g4 &= mask;
+        h0 = (h0 & !mask) | g0;
+        h1 = (h1 & !mask) | g1;
+        h2 = (h2 & !mask) | g2;
+        h3 = (h3 & !mask) | g3;
+        h4 = (h4 & !mask) | g4;
+
+        // h mod 2^128, then add s
+        let words = [
+            h0 | (h1 << 26),
+            (h1 >> 6) | (h2 << 20),
+            (h2 >> 12) | (h3 << 14),
+            (h3 >> 18) | (h4 << 8),
+        ];
+        let mut f = 0u64;
+        for (i, (w, p)) in words.iter().zip(self.pad).enumerate() {
+            f = u64::from(*w) + u64::from(p) + (f >> 32);
+            out[4 * i..][..4].copy_from_slice(&(f as u32).to_le_bytes());
+        }
+    }
+}
+
+impl Zeroize for Poly1305 {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+        self.r.zeroize();
+        self.h.zeroize();
+        self.pad.zeroize();
+    }
+}
+
+impl Drop for Poly1305 {
+    fn drop(&mut self) {
+        self.r.zeroize();
+        self.h.zeroize();
+        self.pad.zeroize();
+    }
+}
+
+impl Digest for Poly1305 {
+    const OUTPUT_SIZE: usize = 16;
+    const BLOCK_SIZE: usize = 16;
+
+    fn init(&mut self) {
+        let k = &self.key;
+        self.r = [
+            le32(&k[0..]) & 0x3ffffff,
+            (le32(&k[3..]) >> 2) & 0x3ffff03,
+            (le32(&k[6..]) >> 4) & 0x3ffc0ff,
+            (le32(&k[9..]) >> 6) & 0x3f03fff,
+            (le32(&k[12..]) >> 8) & 0x00fffff,
+        ];
+        self.pad = [
+            le32(&k[16..]),
+            le32(&k[20..]),
+            le32(&k[24..]),
+            le32(&k[28..]),
+        ];
+        self.h = [0; 5];
+    }
+
+    fn update(&mut self, block: &[u8]) {
+        horner(self, block, Self::add, Self::mul_r);
+    }
+
+    fn do_final(&mut self, lblock: &[u8], out: &mut [u8]) {
+        assert!(lblock.len() <= 16);
+        horner(self, lblock, Self::add, Self::mul_r);
+        self.finalize(out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Poly1305;
+    use crate::digest::digest;
+
+    #[test]
+    pub fn poly1305_rfc8439() {
+        // RFC 8439 section 2.5.2
+        let key = [
+            0x85, 0xd6, 0xbe, 0x78, 0x57, 0x55, 0x6d, 0x33, 0x7f, 0x44, 0x52, 0xfe, 0x42, 0xd5,
+            0x06, 0xa8, 0x01, 0x03, 0x80, 0x8a, 0xfb, 0x0d, 0xb2, 0xfd, 0x4a, 0xbf, 0xf6, 0xaf,
+            0x41, 0x49, 0xf5, 0x1b,
+        ];
+        let mut out = [0u8; 16];
+        digest(
+            Poly1305::new(&key),
+            b"Cryptographic Forum Research Group",
+            &mut out,
+        );
+        assert_eq!(
+            out,
+            [
+                0xa8, 0x06, 0x1d, 0xc1, 0x30, 0x51, 0x36, 0xc6, 0xc2, 0x2b, 0x8b, 0xaf, 0x0c, 0x01,
+                0x27, 0xa9
+            ]
+        );
+    }
+
+    #[test]
+    pub fn poly1305_wraparound() {
+        // RFC 8439 appendix A.3: h+5 overflows 2^130, exercising the final reduction
+        let mut key = [0u8; 32];
+        key[0] = 2;
+        let mut out = [0u8; 16];
+        digest(Poly1305::new(&key), &[0xff; 16], &mut out);
+        let mut expected = [0u8; 16];
+        expected[0] = 3;
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    pub fn poly1305_reset_into() {
+        let mut mac = Poly1305::new(&[1; 32]);
+        let mut out = [0u8; 16];
+        digest(&mut mac, b"first message", &mut out);
+        for key in [[2u8; 32], [3u8; 32]] {
+            let mut expected = [0u8; 16];
+            mac.reset_into(&key);
+            digest(&mut mac, b"second message", &mut out);
+            digest(Poly1305::new(&key), b"second message", &mut expected);
+            assert_eq!(out, expected);
+        }
+    }
+}
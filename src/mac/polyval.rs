@@ -0,0 +1,107 @@
+use zeroize::Zeroize;
+
+use super::field_poly::horner;
+use crate::digest::Digest;
+
+// The low 128 bits of the POLYVAL field polynomial x^128 + x^127 + x^126 + x^121 + 1
+const POLY_LOW: u128 = (1 << 127) | (1 << 126) | (1 << 121) | 1;
+
+///
+/// Computes `a*b*x^-128` in the POLYVAL field of RFC 8452, with each element's bits in little-endian order.
+///
+/// This is a bit-serial Horner evaluation over the bits of `b`, dividing by `x` after each step, so it does the same operations for every input.
+pub(crate) fn polyval_dot(a: u128, b: u128) -> u128 {
+    let mut acc = 0u128;
+    for i in 0..128 {
+        acc ^= a & ((b >> i) & 1).wrapping_neg();
+        let m = (acc & 1).wrapping_neg();
+        acc = ((acc ^ (m & POLY_LOW)) >> 1) | (m & (1 << 127));
+    }
+    acc
+}
+
+///
+/// The POLYVAL universal hash of RFC 8452, the authenticator used by AES-GCM-SIV.
+///
+/// A final partial block passed to [`Digest::do_final`] is zero-padded. Like Poly1305, a key must only be used for one message unless the output is encrypted.
+pub struct Polyval {
+    h: u128,
+    s: u128,
+}
+
+impl Polyval {
+    pub fn new(key: &[u8; 16]) -> Self {
+        Self {
+            h: u128::from_le_bytes(*key),
+            s: 0,
+        }
+    }
+}
+
+impl Zeroize for Polyval {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+        self.s.zeroize();
+    }
+}
+
+impl Drop for Polyval {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Digest for Polyval {
+    const OUTPUT_SIZE: usize = 16;
+    const BLOCK_SIZE: usize = 16;
+
+    fn init(&mut self) {
+        self.s = 0;
+    }
+
+    fn update(&mut self, block: &[u8]) {
+        let h = self.h;
+        horner(
+            &mut self.s,
+            block,
+            |s, x: &[u8; 16], _| *s ^= u128::from_le_bytes(*x),
+            |s| *s = polyval_dot(*s, h),
+        );
+    }
+
+    fn do_final(&mut self, lblock: &[u8], out: &mut [u8]) {
+        assert!(lblock.len() <= 16);
+        self.update(lblock);
+        out[..16].copy_from_slice(&self.s.to_le_bytes());
+        self.s = 0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Polyval;
+    use crate::digest::digest;
+
+    #[test]
+    pub fn polyval_rfc8452() {
+        // RFC 8452 appendix A
+        let h = [
+            0x25, 0x62, 0x93, 0x47, 0x58, 0x92, 0x42, 0x76, 0x1d, 0x31, 0xf8, 0x26, 0xba, 0x4b,
+            0x75, 0x7b,
+        ];
+        let x = [
+            0x4f, 0x4f, 0x95, 0x66, 0x8c, 0x83, 0xdf, 0xb6, 0x40, 0x17, 0x62, 0xbb, 0x2d, 0x01,
+            0xa2, 0x62, 0xd1, 0xa2, 0x4d, 0xdd, 0x27, 0x21, 0xd0, 0x06, 0xbb, 0xe4, 0x5f, 0x20,
+            0xd3, 0xc9, 0xf3, 0x62,
+        ];
+        let mut out = [0u8; 16];
+        digest(Polyval::new(&h), &x, &mut out);
+        assert_eq!(
+            out,
+            [
+                0xf7, 0xa3, 0xb4, 0x7b, 0x84, 0x61, 0x19, 0xfa, 0xe5, 0xb7, 0x86, 0x6c, 0xf5, 0xe5,
+                0xb7, 0x7e
+            ]
+        );
+    }
+}
@@ -0,0 +1,403 @@
+use core::hash::{BuildHasher, Hasher};
+
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::digest::Digest;
+
+///
+/// A keyed hash implementing the SipHash-C-D family, with a `BITS`-bit output (64 or 128).
+///
+/// SipHash is a fast pseudorandom function for short inputs, designed to protect hash tables against collision flooding.
+/// It is not a general purpose collision-resistant hash, and the 64-bit variants should not be used as a MAC where forgeries must be infeasible.
+///
+/// Messages can be hashed through the [`Digest`] impl, or, for the 64-bit variants, through [`core::hash::Hasher`].
+pub struct SipHash<const C: usize, const D: usize, const BITS: usize> {
+    key: Zeroizing<[u8; 16]>,
+    v: [u64; 4],
+    size: u64,
+    tail: [u8; 8],
+    tail_len: usize,
+}
+
+impl<const C: usize, const D: usize, const BITS: usize> SipHash<C, D, BITS> {
+    pub fn new(key: &[u8; 16]) -> Self {
+        let mut ret = Self {
+            key: Zeroizing::new(*key),
+            v: [0; 4],
+            size: 0,
+            tail: [0; 8],
+            tail_len: 0,
+        };
+        ret.init();
+        ret
+    }
+
+    ///
+    /// Replaces the key and restarts the hash, as if `self` had been constructed by [`SipHash::new`]
+    pub fn reset_into(&mut self, key: &[u8; 16]) {
+        *self.key = *key;
+        self.init();
+    }
+
+    fn compress(v: &mut [u64; 4], m: u64, rounds: usize) {
+        v[3] ^= m;
+        for _ in 0..rounds {
+            sip_round(v);
+        }
+        v[0] ^= m;
+    }
+
+    fn finalize(&self, lblock: &[u8], out: &mut [u8]) {
+        let mut v = Zeroizing::new(self.v);
+        let mut b = [0u8; 8];
+        b[..lblock.len()].copy_from_slice(lblock);
+        b[7] = self.size.wrapping_add(lblock.len() as u64) as u8;
+        Self::compress(&mut v, u64::from_le_bytes(b), C);
+        b.zeroize();
+        v[2] ^= if BITS == 128 { 0xee } else { 0xff };
+        for _ in 0..D {
+            sip_round(&mut v);
+        }
+        out[..8].copy_from_slice(&(v[0] ^ v[1] ^ v[2] ^ v[3]).to_le_bytes());
+        if BITS == 128 {
+            v[1] ^= 0xdd;
+            for _ in 0..D {
+                sip_round(&mut v);
+            }
+            out[8..].copy_from_slice(&(v[0] ^ v[1] ^ v[2] ^ v[3]).to_le_bytes());
+        }
+    }
+}
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13);
+    v[1] ^= v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16);
+    v[3] ^= v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21);
+    v[3] ^= v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17);
+    v[1] ^= v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+impl<const C: usize, const D: usize, const BITS: usize> Zeroize for SipHash<C, D, BITS> {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+        self.v.zeroize();
+        self.tail.zeroize();
+    }
+}
+
+impl<const C: usize, const D: usize, const BITS: usize> Drop for SipHash<C, D, BITS> {
+    fn drop(&mut self) {
+        self.v.zeroize();
+        self.tail.zeroize();
+    }
+}
+
+impl<const C: usize, const D: usize, const BITS: usize> Digest for SipHash<C, D, BITS> {
+    const OUTPUT_SIZE: usize = BITS / 8;
+    const BLOCK_SIZE: usize = 8;
+
+    fn init(&mut self) {
+        let k0 = u64::from_le_bytes([
+            self.key[0],
+            self.key[1],
+            self.key[2],
+            self.key[3],
+            self.key[4],
+            self.key[5],
+            self.key[6],
+            self.key[7],
+        ]);
+        let k1 = u64::from_le_bytes([
+            self.key[8],
+            self.key[9],
+            self.key[10],
+            self.key[11],
+            self.key[12],
+            self.key[13],
+            self.key[14],
+            self.key[15],
+        ]);
+        self.v = [
+            k0 ^ 0x736f6d6570736575,
+            k1 ^ 0x646f72616e646f6d,
+            k0 ^ 0x6c7967656e657261,
+            k1 ^ 0x7465646279746573,
+        ];
+        if BITS == 128 {
+            self.v[1] ^= 0xee;
+        }
+        self.size = 0;
+        self.tail.zeroize();
+        self.tail_len = 0;
+    }
+
+    fn update(&mut self, block: &[u8]) {
+        let mut m = [0u8; 8];
+        m.copy_from_slice(block);
+        Self::compress(&mut self.v, u64::from_le_bytes(m), C);
+        m.zeroize();
+        self.size = self.size.wrapping_add(8);
+    }
+
+    fn do_final(&mut self, mut lblock: &[u8], out: &mut [u8]) {
+        assert!(lblock.len() <= 8);
+        if lblock.len() == 8 {
+            self.update(lblock);
+            lblock = &[];
+        }
+        self.finalize(lblock, out);
+    }
+}
+
+impl<const C: usize, const D: usize> Hasher for SipHash<C, D, 64> {
+    fn write(&mut self, mut bytes: &[u8]) {
+        if self.tail_len != 0 {
+            let len = (8 - self.tail_len).min(bytes.len());
+            self.tail[self.tail_len..][..len].copy_from_slice(&bytes[..len]);
+            self.tail_len += len;
+            bytes = &bytes[len..];
+            if self.tail_len < 8 {
+                return;
+            }
+            let tail = self.tail;
+            self.update(&tail);
+            self.tail_len = 0;
+        }
+        let mut blocks = bytes.chunks_exact(8);
+        for block in &mut blocks {
+            self.update(block);
+        }
+        let rest = blocks.remainder();
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_len = rest.len();
+    }
+
+    fn finish(&self) -> u64 {
+        let mut out = [0u8; 8];
+        self.finalize(&self.tail[..self.tail_len], &mut out);
+        u64::from_le_bytes(out)
+    }
+}
+
+///
+/// A [`BuildHasher`] producing [`SipHash`] hashers under a fixed key, for use with `HashMap` and `HashSet`.
+///
+/// The key should be chosen at random (for example, from a [`crate::rand::SecureRandom`]) so that an attacker cannot predict collisions.
+pub struct BuildSipHasher<const C: usize, const D: usize> {
+    key: Zeroizing<[u8; 16]>,
+}
+
+impl<const C: usize, const D: usize> BuildSipHasher<C, D> {
+    pub fn new(key: &[u8; 16]) -> Self {
+        Self {
+            key: Zeroizing::new(*key),
+        }
+    }
+}
+
+impl<const C: usize, const D: usize> BuildHasher for BuildSipHasher<C, D> {
+    type Hasher = SipHash<C, D, 64>;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        SipHash::new(&self.key)
+    }
+}
+
+pub type SipHash13 = SipHash<1, 3, 64>;
+pub type SipHash24 = SipHash<2, 4, 64>;
+pub type SipHash13_128 = SipHash<1, 3, 128>;
+pub type SipHash24_128 = SipHash<2, 4, 128>;
+
+///
+/// Computes the SipHash-2-4 of `data` under `key`
+pub fn siphash(key: &[u8; 16], data: &[u8]) -> u64 {
+    let mut out = [0u8; 8];
+    crate::digest::digest(SipHash24::new(key), data, &mut out);
+    u64::from_le_bytes(out)
+}
+
+#[cfg(test)]
+mod test {
+    use core::hash::{BuildHasher, Hash, Hasher};
+
+    use super::{BuildSipHasher, SipHash13, SipHash24, SipHash24_128};
+
+    const KEY: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+
+    fn input(len: usize) -> [u8; 64] {
+        let mut ret = [0u8; 64];
+        for (i, v) in ret.iter_mut().enumerate().take(len) {
+            *v = i as u8;
+        }
+        ret
+    }
+
+    const SIPHASH24_64: [[u8; 8]; 64] = [
+        [0x31, 0x0e, 0x0e, 0xdd, 0x47, 0xdb, 0x6f, 0x72],
+        [0xfd, 0x67, 0xdc, 0x93, 0xc5, 0x39, 0xf8, 0x74],
+        [0x5a, 0x4f, 0xa9, 0xd9, 0x09, 0x80, 0x6c, 0x0d],
+        [0x2d, 0x7e, 0xfb, 0xd7, 0x96, 0x66, 0x67, 0x85],
+        [0xb7, 0x87, 0x71, 0x27, 0xe0, 0x94, 0x27, 0xcf],
+        [0x8d, 0xa6, 0x99, 0xcd, 0x64, 0x55, 0x76, 0x18],
+        [0xce, 0xe3, 0xfe, 0x58, 0x6e, 0x46, 0xc9, 0xcb],
+        [0x37, 0xd1, 0x01, 0x8b, 0xf5, 0x00, 0x02, 0xab],
+        [0x62, 0x24, 0x93, 0x9a, 0x79, 0xf5, 0xf5, 0x93],
+        [0xb0, 0xe4, 0xa9, 0x0b, 0xdf, 0x82, 0x00, 0x9e],
+        [0xf3, 0xb9, 0xdd, 0x94, 0xc5, 0xbb, 0x5d, 0x7a],
+        [0xa7, 0xad, 0x6b, 0x22, 0x46, 0x2f, 0xb3, 0xf4],
+        [0xfb, 0xe5, 0x0e, 0x86, 0xbc, 0x8f, 0x1e, 0x75],
+        [0x90, 0x3d, 0x84, 0xc0, 0x27, 0x56, 0xea, 0x14],
+        [0xee, 0xf2, 0x7a, 0x8e, 0x90, 0xca, 0x23, 0xf7],
+        [0xe5, 0x45, 0xbe, 0x49, 0x61, 0xca, 0x29, 0xa1],
+        [0xdb, 0x9b, 0xc2, 0x57, 0x7f, 0xcc, 0x2a, 0x3f],
+        [0x94, 0x47, 0xbe, 0x2c, 0xf5, 0xe9, 0x9a, 0x69],
+        [0x9c, 0xd3, 0x8d, 0x96, 0xf0, 0xb3, 0xc1, 0x4b],
+        [0xbd, 0x61, 0x79, 0xa7, 0x1d, 0xc9, 0x6d, 0xbb],
+        [0x98, 0xee, 0xa2, 0x1a, 0xf2, 0x5c, 0xd6, 0xbe],
+        [0xc7, 0x67, 0x3b, 0x2e, 0xb0, 0xcb, 0xf2, 0xd0],
+        [0x88, 0x3e, 0xa3, 0xe3, 0x95, 0x67, 0x53, 0x93],
+        [0xc8, 0xce, 0x5c, 0xcd, 0x8c, 0x03, 0x0c, 0xa8],
+        [0x94, 0xaf, 0x49, 0xf6, 0xc6, 0x50, 0xad, 0xb8],
+        [0xea, 0xb8, 0x85, 0x8a, 0xde, 0x92, 0xe1, 0xbc],
+        [0xf3, 0x15, 0xbb, 0x5b, 0xb8, 0x35, 0xd8, 0x17],
+        [0xad, 0xcf, 0x6b, 0x07, 0x63, 0x61, 0x2e, 0x2f],
+        [0xa5, 0xc9, 0x1d, 0xa7, 0xac, 0xaa, 0x4d, 0xde],
+        [0x71, 0x65, 0x95, 0x87, 0x66, 0x50, 0xa2, 0xa6],
+        [0x28, 0xef, 0x49, 0x5c, 0x53, 0xa3, 0x87, 0xad],
+        [0x42, 0xc3, 0x41, 0xd8, 0xfa, 0x92, 0xd8, 0x32],
+        [0xce, 0x7c, 0xf2, 0x72, 0x2f, 0x51, 0x27, 0x71],
+        [0xe3, 0x78, 0x59, 0xf9, 0x46, 0x23, 0xf3, 0xa7],
+        [0x38, 0x12, 0x05, 0xbb, 0x1a, 0xb0, 0xe0, 0x12],
+        [0xae, 0x97, 0xa1, 0x0f, 0xd4, 0x34, 0xe0, 0x15],
+        [0xb4, 0xa3, 0x15, 0x08, 0xbe, 0xff, 0x4d, 0x31],
+        [0x81, 0x39, 0x62, 0x29, 0xf0, 0x90, 0x79, 0x02],
+        [0x4d, 0x0c, 0xf4, 0x9e, 0xe5, 0xd4, 0xdc, 0xca],
+        [0x5c, 0x73, 0x33, 0x6a, 0x76, 0xd8, 0xbf, 0x9a],
+        [0xd0, 0xa7, 0x04, 0x53, 0x6b, 0xa9, 0x3e, 0x0e],
+        [0x92, 0x59, 0x58, 0xfc, 0xd6, 0x42, 0x0c, 0xad],
+        [0xa9, 0x15, 0xc2, 0x9b, 0xc8, 0x06, 0x73, 0x18],
+        [0x95, 0x2b, 0x79, 0xf3, 0xbc, 0x0a, 0xa6, 0xd4],
+        [0xf2, 0x1d, 0xf2, 0xe4, 0x1d, 0x45, 0x35, 0xf9],
+        [0x87, 0x57, 0x75, 0x19, 0x04, 0x8f, 0x53, 0xa9],
+        [0x10, 0xa5, 0x6c, 0xf5, 0xdf, 0xcd, 0x9a, 0xdb],
+        [0xeb, 0x75, 0x09, 0x5c, 0xcd, 0x98, 0x6c, 0xd0],
+        [0x51, 0xa9, 0xcb, 0x9e, 0xcb, 0xa3, 0x12, 0xe6],
+        [0x96, 0xaf, 0xad, 0xfc, 0x2c, 0xe6, 0x66, 0xc7],
+        [0x72, 0xfe, 0x52, 0x97, 0x5a, 0x43, 0x64, 0xee],
+        [0x5a, 0x16, 0x45, 0xb2, 0x76, 0xd5, 0x92, 0xa1],
+        [0xb2, 0x74, 0xcb, 0x8e, 0xbf, 0x87, 0x87, 0x0a],
+        [0x6f, 0x9b, 0xb4, 0x20, 0x3d, 0xe7, 0xb3, 0x81],
+        [0xea, 0xec, 0xb2, 0xa3, 0x0b, 0x22, 0xa8, 0x7f],
+        [0x99, 0x24, 0xa4, 0x3c, 0xc1, 0x31, 0x57, 0x24],
+        [0xbd, 0x83, 0x8d, 0x3a, 0xaf, 0xbf, 0x8d, 0xb7],
+        [0x0b, 0x1a, 0x2a, 0x32, 0x65, 0xd5, 0x1a, 0xea],
+        [0x13, 0x50, 0x79, 0xa3, 0x23, 0x1c, 0xe6, 0x60],
+        [0x93, 0x2b, 0x28, 0x46, 0xe4, 0xd7, 0x06, 0x66],
+        [0xe1, 0x91, 0x5f, 0x5c, 0xb1, 0xec, 0xa4, 0x6c],
+        [0xf3, 0x25, 0x96, 0x5c, 0xa1, 0x6d, 0x62, 0x9f],
+        [0x57, 0x5f, 0xf2, 0x8e, 0x60, 0x38, 0x1b, 0xe5],
+        [0x72, 0x45, 0x06, 0xeb, 0x4c, 0x32, 0x8a, 0x95],
+    ];
+    const SIPHASH24_128: [[u8; 16]; 4] = [
+        [
+            0xa3, 0x81, 0x7f, 0x04, 0xba, 0x25, 0xa8, 0xe6, 0x6d, 0xf6, 0x72, 0x14, 0xc7, 0x55,
+            0x02, 0x93,
+        ],
+        [
+            0xda, 0x87, 0xc1, 0xd8, 0x6b, 0x99, 0xaf, 0x44, 0x34, 0x76, 0x59, 0x11, 0x9b, 0x22,
+            0xfc, 0x45,
+        ],
+        [
+            0x81, 0x77, 0x22, 0x8d, 0xa4, 0xa4, 0x5d, 0xc7, 0xfc, 0xa3, 0x8b, 0xde, 0xf6, 0x0a,
+            0xff, 0xe4,
+        ],
+        [
+            0x9c, 0x70, 0xb6, 0x0c, 0x52, 0x67, 0xa9, 0x4e, 0x5f, 0x33, 0xb6, 0xb0, 0x29, 0x85,
+            0xed, 0x51,
+        ],
+    ];
+
+    #[test]
+    fn siphash24_reference_vectors() {
+        for (len, expected) in SIPHASH24_64.iter().enumerate() {
+            let mut out = [0u8; 8];
+            crate::digest::digest(SipHash24::new(&KEY), &input(len)[..len], &mut out);
+            assert_eq!(&out, expected, "length {}", len);
+            assert_eq!(
+                super::siphash(&KEY, &input(len)[..len]),
+                u64::from_le_bytes(*expected)
+            );
+        }
+    }
+
+    #[test]
+    fn siphash24_128_reference_vectors() {
+        for (len, expected) in SIPHASH24_128.iter().enumerate() {
+            let mut out = [0u8; 16];
+            crate::digest::digest(SipHash24_128::new(&KEY), &input(len)[..len], &mut out);
+            assert_eq!(&out, expected, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn siphash13_test() {
+        let mut out = [0u8; 8];
+        crate::digest::digest(SipHash13::new(&KEY), &[], &mut out);
+        assert_eq!(out, [0xdc, 0xc4, 0x0f, 0x05, 0x58, 0x01, 0xac, 0xab]);
+        crate::digest::digest(SipHash13::new(&KEY), &input(15)[..15], &mut out);
+        assert_eq!(out, [0x56, 0x99, 0x51, 0x2a, 0x6d, 0xd8, 0x20, 0xd3]);
+    }
+
+    #[test]
+    fn siphash24_hasher_split_writes() {
+        for (len, expected) in SIPHASH24_64.iter().enumerate() {
+            let input = input(len);
+            for split in 0..=len {
+                let mut hasher = SipHash24::new(&KEY);
+                hasher.write(&input[..split]);
+                for b in &input[split..len] {
+                    hasher.write(core::slice::from_ref(b));
+                }
+                assert_eq!(hasher.finish(), u64::from_le_bytes(*expected));
+            }
+        }
+    }
+
+    #[test]
+    fn siphash_build_hasher() {
+        let builder = BuildSipHasher::<2, 4>::new(&KEY);
+        let mut hasher = SipHash24::new(&KEY);
+        "The quick brown fox jumps over the lazy dog".hash(&mut hasher);
+        assert_eq!(
+            builder.hash_one("The quick brown fox jumps over the lazy dog"),
+            hasher.finish()
+        );
+
+        let mut map = std::collections::HashMap::with_hasher(builder);
+        map.insert("key", 1);
+        map.insert("other", 2);
+        assert_eq!(map.get("key"), Some(&1));
+        assert_eq!(map.get("other"), Some(&2));
+    }
+
+    #[test]
+    fn siphash24_reset_into() {
+        let mut hasher = SipHash24::new(&[0xff; 16]);
+        hasher.write(b"discarded");
+        hasher.reset_into(&KEY);
+        hasher.write(&input(15)[..15]);
+        assert_eq!(hasher.finish(), u64::from_le_bytes(SIPHASH24_64[15]));
+    }
+}
@@ -0,0 +1,64 @@
+///
+/// Copies `src` into `dst` if `cond` is true, and leaves `dst` unchanged otherwise.
+///
+/// Every byte of both slices is read and every byte of `dst` is written regardless of `cond`, and the copy does not branch on `cond`,
+///  so this can be used to commit a result only when a secret condition holds (for example, when a tag verifies).
+///
+/// Panics if `dst.len()!=src.len()`
+///
+/// ## Examples
+///
+/// ```
+/// let mut dst = [1, 2, 3];
+/// lc_crypto::mem::conditional_copy(&mut dst, &[4, 5, 6], false);
+/// assert_eq!(dst, [1, 2, 3]);
+/// lc_crypto::mem::conditional_copy(&mut dst, &[4, 5, 6], true);
+/// assert_eq!(dst, [4, 5, 6]);
+/// ```
+pub fn conditional_copy(dst: &mut [u8], src: &[u8], cond: bool) {
+    assert_eq!(dst.len(), src.len());
+    // The mask comes from the asm primitive, so the optimizer cannot see that it is all-zero or all-one and turn the loop into a conditional memcpy
+    let mask = crate::asm::ct_eq_mask(cond as usize, 1) as u8;
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= mask & (*d ^ s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    pub fn conditional_copy_false() {
+        let src: [u8; 256] = core::array::from_fn(|i| i as u8);
+        let mut dst = [0xa5u8; 256];
+        super::conditional_copy(&mut dst, &src, false);
+        assert_eq!(dst, [0xa5u8; 256]);
+    }
+
+    #[test]
+    pub fn conditional_copy_true() {
+        let src: [u8; 256] = core::array::from_fn(|i| i as u8);
+        let mut dst = [0xa5u8; 256];
+        super::conditional_copy(&mut dst, &src, true);
+        assert_eq!(dst, src);
+    }
+
+    #[test]
+    pub fn conditional_copy_every_byte_pair() {
+        // The result depends only on the bytes and the condition, with no special cases for particular values
+        for a in 0..=255u8 {
+            for b in 0..=255u8 {
+                let mut dst = [a];
+                super::conditional_copy(&mut dst, &[b], false);
+                assert_eq!(dst[0], a);
+                super::conditional_copy(&mut dst, &[b], true);
+                assert_eq!(dst[0], b);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn conditional_copy_diff_sizes() {
+        super::conditional_copy(&mut [0; 3], &[0; 4], true);
+    }
+}
@@ -0,0 +1,148 @@
+//!
+//! One-time passwords: HOTP (RFC 4226) and TOTP (RFC 6238).
+//!
+//! The core functions take the counter or the current time as a parameter, so they work without `std`. `totp_now` reads the system clock under the `std` feature.
+
+use alloc::vec;
+use zeroize::Zeroizing;
+
+use crate::{
+    asm::ct_eq_mask,
+    digest::{digest, Digest, Hmac},
+    error::{Error, ErrorKind, Result},
+};
+
+///
+/// Computes the HOTP value of RFC 4226 for `key` and `counter`, as a number of `digits` decimal digits (1 to 9).
+///
+/// The digest is HMAC-`D`; RFC 4226 uses SHA-1, and RFC 6238 also allows SHA-256 and SHA-512.
+/// The dynamic truncation selects its 4 bytes by masking over every possible offset, so the memory access pattern does not depend on the HMAC output.
+///
+/// Returns an error of kind [`ErrorKind::InvalidInput`] if `digits` is out of range.
+pub fn hotp<D: Digest + Default>(key: &[u8], counter: u64, digits: u8) -> Result<u32> {
+    if !(1..=9).contains(&digits) || D::OUTPUT_SIZE < 20 {
+        return Err(Error::new_with_message(
+            ErrorKind::InvalidInput,
+            "HOTP needs 1 to 9 digits and at least a 160-bit digest",
+        ));
+    }
+    let mut mac = Zeroizing::new(vec![0u8; D::OUTPUT_SIZE]);
+    digest(
+        Hmac::new(D::default(), key),
+        &counter.to_be_bytes(),
+        &mut mac,
+    );
+    let offset = usize::from(mac[D::OUTPUT_SIZE - 1] & 0xf);
+    let mut code = [0u8; 4];
+    for (i, window) in mac.windows(4).take(16).enumerate() {
+        let mask = ct_eq_mask(i, offset) as u8;
+        for (c, &b) in code.iter_mut().zip(window) {
+            *c |= b & mask;
+        }
+    }
+    let code = u32::from_be_bytes(code) & 0x7fff_ffff;
+    Ok(code % 10u32.pow(u32::from(digits)))
+}
+
+///
+/// Computes the TOTP value of RFC 6238 at the time `unix_seconds`, with time steps of `step` seconds counted from `t0`.
+///
+/// RFC 6238 recommends `step=30` and `t0=0`. The value is the [`hotp`] of `(unix_seconds-t0)/step`.
+///
+/// Returns an error of kind [`ErrorKind::InvalidInput`] if `step` is zero, `unix_seconds` is before `t0`, or `digits` is out of range.
+pub fn totp_at<D: Digest + Default>(
+    key: &[u8],
+    unix_seconds: u64,
+    step: u32,
+    t0: u64,
+    digits: u8,
+) -> Result<u32> {
+    if step == 0 || unix_seconds < t0 {
+        return Err(Error::new_with_message(
+            ErrorKind::InvalidInput,
+            "TOTP time is before t0, or the step is zero",
+        ));
+    }
+    hotp::<D>(key, (unix_seconds - t0) / u64::from(step), digits)
+}
+
+///
+/// Computes the TOTP value for the current system time, as [`totp_at`].
+///
+/// Returns an error of kind [`ErrorKind::Other`] if the system clock is before the Unix epoch.
+#[cfg(any(test, feature = "std"))]
+pub fn totp_now<D: Digest + Default>(key: &[u8], step: u32, t0: u64, digits: u8) -> Result<u32> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|_| Error::new_with_message(ErrorKind::Other, "system clock is before 1970"))?;
+    totp_at::<D>(key, now.as_secs(), step, t0, digits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{hotp, totp_at, totp_now};
+    #[cfg(feature = "sha1")]
+    use crate::digest::sha1::Sha1;
+    use crate::{digest::sha2::Sha256, error::ErrorKind};
+
+    const TIMES: [u64; 6] = [
+        59,
+        1111111109,
+        1111111111,
+        1234567890,
+        2000000000,
+        20000000000,
+    ];
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    pub fn hotp_rfc4226() {
+        // RFC 4226 appendix D
+        let expected = [
+            755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489,
+        ];
+        for (counter, &code) in expected.iter().enumerate() {
+            assert_eq!(
+                hotp::<Sha1>(b"12345678901234567890", counter as u64, 6).unwrap(),
+                code
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    pub fn totp_rfc6238_sha1() {
+        // RFC 6238 appendix B
+        let expected = [94287082, 7081804, 14050471, 89005924, 69279037, 65353130];
+        for (&t, &code) in TIMES.iter().zip(&expected) {
+            assert_eq!(
+                totp_at::<Sha1>(b"12345678901234567890", t, 30, 0, 8).unwrap(),
+                code
+            );
+        }
+    }
+
+    #[test]
+    pub fn totp_rfc6238_sha256() {
+        // RFC 6238 appendix B
+        let key = b"12345678901234567890123456789012";
+        let expected = [46119246, 68084774, 67062674, 91819424, 90698825, 77737706];
+        for (&t, &code) in TIMES.iter().zip(&expected) {
+            assert_eq!(totp_at::<Sha256>(key, t, 30, 0, 8).unwrap(), code);
+        }
+    }
+
+    #[test]
+    pub fn totp_invalid() {
+        let key = b"12345678901234567890";
+        for r in [
+            totp_at::<Sha256>(key, 59, 0, 0, 6),
+            totp_at::<Sha256>(key, 59, 30, 60, 6),
+            totp_at::<Sha256>(key, 59, 30, 0, 0),
+            totp_at::<Sha256>(key, 59, 30, 0, 10),
+        ] {
+            assert_eq!(r.unwrap_err().kind(), ErrorKind::InvalidInput);
+        }
+        assert!(totp_now::<Sha256>(key, 30, 0, 6).unwrap() < 1_000_000);
+    }
+}
@@ -1,7 +1,7 @@
-use alloc::{boxed::Box, vec};
+use alloc::{boxed::Box, vec, vec::Vec};
 use zeroize::Zeroizing;
 
-use crate::digest::Digest;
+use crate::{digest::Digest, secret::SecretUint};
 
 pub struct Seeds<'a, SR: ?Sized>(&'a mut SR);
 
@@ -27,6 +27,42 @@ pub trait SecureRandom {
     {
         Seeds(self)
     }
+
+    ///
+    /// Returns a uniformly random `u32`, from 4 bytes of output read as little-endian
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.next_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    ///
+    /// Returns a uniformly random `u64`, from 8 bytes of output read as little-endian
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.next_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    ///
+    /// Returns a uniformly random value in `lo..hi`.
+    ///
+    /// Values which would bias the result towards the low end of the range are rejected and redrawn,
+    ///  so the result is exactly uniform. Fewer than half of all draws are rejected, whatever the range.
+    ///
+    /// Panics if `lo>=hi`
+    fn next_in_range(&mut self, lo: u64, hi: u64) -> u64 {
+        assert!(lo < hi, "empty range");
+        let span = hi - lo;
+        // 2^64 mod span: the number of values at the bottom of the u64 range which would be over-represented
+        let threshold = span.wrapping_neg() % span;
+        loop {
+            let x = self.next_u64();
+            if x >= threshold {
+                return lo + x % span;
+            }
+        }
+    }
 }
 
 impl<SR: SecureRandom + ?Sized> SecureRandom for &mut SR {
@@ -76,6 +112,20 @@ impl<D1: Digest, D2: Digest> DoubleDigestRandom<D1, D2> {
         }
         crate::digest::digest(&mut self.output, &inter, output);
     }
+
+    ///
+    /// Seeds the generator as [`SecureRandom::seed`], then mixes in the domain string `domain`.
+    ///
+    /// Generators seeded with the same seed but different domains produce independent streams, so one master seed can be shared by several subsystems.
+    /// The state is replaced with `D1(len(domain) || domain || state)`, with the length as 8 little-endian bytes, so even an empty domain gives a different stream from [`SecureRandom::seed`].
+    pub fn seed_personalized<I: IntoIterator<Item = u64>>(&mut self, domain: &[u8], seed: I) {
+        self.seed(seed);
+        let mut input = Zeroizing::new(Vec::with_capacity(8 + domain.len() + self.state1.len()));
+        input.extend_from_slice(&(domain.len() as u64).to_le_bytes());
+        input.extend_from_slice(domain);
+        input.extend_from_slice(&self.state1);
+        crate::digest::digest(&mut self.update, &input, &mut self.state1);
+    }
 }
 
 impl<D1: Digest, D2: Digest> SecureRandom for DoubleDigestRandom<D1, D2> {
@@ -102,4 +152,211 @@ impl<D1: Digest, D2: Digest> SecureRandom for DoubleDigestRandom<D1, D2> {
     }
 }
 
+///
+/// Shuffles `slice` into a uniformly random order with the Fisher-Yates algorithm, drawing indices with [`SecureRandom::next_in_range`].
+///
+/// The indices depend only on the output of `rand`, never on the contents of `slice`, so shuffling reveals nothing about the data;
+///  the permutation itself is only as secret as the state of `rand`.
+pub fn shuffle<T, R: SecureRandom + ?Sized>(slice: &mut [T], rand: &mut R) {
+    for i in (1..slice.len()).rev() {
+        let j = rand.next_in_range(0, i as u64 + 1) as usize;
+        slice.swap(i, j);
+    }
+}
+
+///
+/// Generates a uniformly random scalar in `[1, modulus)`, as needed for elliptic curve and Diffie-Hellman private keys.
+///
+/// Candidates have the same bit length as `modulus`, and are rejected until one is nonzero and less than `modulus`. The comparison is constant-time,
+///  so the only thing revealed about the result is how many candidates were drawn, which is independent of the accepted value.
+/// For a `bits`-bit modulus, each candidate is rejected with probability at most 1/2 + 2^-bits, which is only far from 1/2 for tiny moduli.
+///
+/// The modulus itself is treated as public. Panics if `modulus<=1`.
+pub fn gen_scalar_nonzero<R: SecureRandom + ?Sized, const N: usize>(
+    rand: &mut R,
+    modulus: &SecretUint<N>,
+) -> SecretUint<N> {
+    let m = modulus.as_be_bytes();
+    let top = m.iter().position(|&b| b != 0).expect("zero modulus");
+    assert!(top < N - 1 || m[top] > 1, "modulus must be greater than 1");
+    let top_mask = 0xffu8 >> m[top].leading_zeros();
+    let zero = SecretUint::zero();
+    loop {
+        let mut bytes = Zeroizing::new([0u8; N]);
+        rand.next_bytes(&mut bytes[top..]);
+        bytes[top] &= top_mask;
+        let candidate = SecretUint::from_be_bytes(*bytes);
+        if candidate.ct_lt(modulus) & !candidate.ct_eq(&zero) {
+            return candidate;
+        }
+    }
+}
+
+pub mod ctr;
 pub mod system;
+
+#[cfg(test)]
+mod test {
+    use alloc::vec::Vec;
+
+    use super::{gen_scalar_nonzero, shuffle, DoubleDigestRandom, SecureRandom};
+    use crate::digest::sha2::Sha256;
+    use crate::secret::SecretUint;
+
+    fn seeded() -> DoubleDigestRandom<Sha256, Sha256> {
+        let mut rand = DoubleDigestRandom::new(Sha256::new(), Sha256::new());
+        rand.seed([0x0123456789abcdef, 42]);
+        rand
+    }
+
+    // Replays a fixed list of u64 values
+    struct Scripted(Vec<u64>);
+
+    impl SecureRandom for Scripted {
+        const STATE_SIZE: usize = 0;
+        fn seed<I: IntoIterator<Item = u64>>(&mut self, _: I) {}
+        fn next_bytes(&mut self, out: &mut [u8]) {
+            let v = self.0.remove(0).to_le_bytes();
+            out.copy_from_slice(&v[..out.len()]);
+        }
+    }
+
+    #[test]
+    pub fn next_words_little_endian() {
+        let mut rand = Scripted(alloc::vec![0x1122334455667788, 0x0102030405060708]);
+        assert_eq!(rand.next_u32(), 0x55667788);
+        assert_eq!(rand.next_u64(), 0x0102030405060708);
+    }
+
+    #[test]
+    pub fn next_in_range_rejects_biased_values() {
+        // 2^64 mod 3 == 1, so only 0 is rejected
+        let mut rand = Scripted(alloc::vec![0, 0, 5]);
+        assert_eq!(rand.next_in_range(10, 13), 12);
+        assert!(rand.0.is_empty());
+        let mut rand = Scripted(alloc::vec![u64::MAX]);
+        assert_eq!(rand.next_in_range(0, u64::MAX), 0);
+    }
+
+    #[test]
+    pub fn next_in_range_bounds() {
+        let mut rand = seeded();
+        for (lo, hi) in [
+            (0, 1),
+            (5, 6),
+            (0, 7),
+            (100, 1000),
+            (u64::MAX - 3, u64::MAX),
+        ] {
+            for _ in 0..200 {
+                let x = rand.next_in_range(lo, hi);
+                assert!(lo <= x && x < hi, "{} not in {}..{}", x, lo, hi);
+            }
+        }
+    }
+
+    #[test]
+    pub fn next_in_range_distribution() {
+        let mut rand = seeded();
+        let mut counts = [0u32; 10];
+        let n = 10000;
+        for _ in 0..n {
+            counts[rand.next_in_range(0, 10) as usize] += 1;
+        }
+        let expected = f64::from(n) / 10.0;
+        let chi2: f64 = counts
+            .iter()
+            .map(|&c| (f64::from(c) - expected).powi(2) / expected)
+            .sum();
+        // The 99.9th percentile of chi-squared with 9 degrees of freedom
+        assert!(chi2 < 27.88, "chi-squared {} for {:?}", chi2, counts);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn next_in_range_empty() {
+        seeded().next_in_range(3, 3);
+    }
+
+    #[test]
+    pub fn shuffle_is_permutation() {
+        let mut rand = seeded();
+        let original: Vec<u8> = (0..=255).collect();
+        let mut data = original.clone();
+        shuffle(&mut data, &mut rand);
+        assert_ne!(data, original);
+        let mut sorted = data.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, original);
+
+        let mut empty: [u8; 0] = [];
+        shuffle(&mut empty, &mut rand);
+        let mut one = [7u8];
+        shuffle(&mut one, &mut rand);
+        assert_eq!(one, [7]);
+    }
+
+    #[test]
+    pub fn shuffle_fixed_seed_deterministic() {
+        let mut a: Vec<u8> = (0..32).collect();
+        let mut b = a.clone();
+        shuffle(&mut a, &mut seeded());
+        shuffle(&mut b, &mut seeded());
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    pub fn double_digest_personalized() {
+        let stream = |domain: Option<&[u8]>| {
+            let mut rand = DoubleDigestRandom::new(Sha256::new(), Sha256::new());
+            match domain {
+                Some(domain) => rand.seed_personalized(domain, [0x0123456789abcdef, 42]),
+                None => rand.seed([0x0123456789abcdef, 42]),
+            }
+            let mut out = [0u8; 48];
+            rand.next_bytes(&mut out);
+            out
+        };
+        let a = stream(Some(b"disk encryption"));
+        assert_eq!(a, stream(Some(b"disk encryption")));
+        assert_ne!(a, stream(Some(b"session tokens")));
+        assert_ne!(stream(Some(b"")), stream(None));
+    }
+
+    #[test]
+    pub fn gen_scalar_nonzero_range() {
+        let mut rand = seeded();
+        let modulus = SecretUint::from_be_bytes([251]);
+        for _ in 0..2000 {
+            let x = gen_scalar_nonzero(&mut rand, &modulus).as_be_bytes()[0];
+            assert!((1..251).contains(&x));
+        }
+        let modulus = SecretUint::from_be_bytes([0, 0, 2]);
+        for _ in 0..100 {
+            assert_eq!(
+                *gen_scalar_nonzero(&mut rand, &modulus).as_be_bytes(),
+                [0, 0, 1]
+            );
+        }
+    }
+
+    #[test]
+    pub fn gen_scalar_nonzero_uniform() {
+        // A rough chi-squared test over the 6 possible values; the 0.1% critical value for 5 degrees of freedom is 20.5
+        let mut rand = seeded();
+        let modulus = SecretUint::from_be_bytes([0, 7]);
+        let mut counts = [0u32; 7];
+        let draws = 6000;
+        for _ in 0..draws {
+            let x = u16::from_be_bytes(*gen_scalar_nonzero(&mut rand, &modulus).as_be_bytes());
+            counts[x as usize] += 1;
+        }
+        assert_eq!(counts[0], 0);
+        let expected = f64::from(draws) / 6.0;
+        let chi2: f64 = counts[1..]
+            .iter()
+            .map(|&c| (f64::from(c) - expected).powi(2) / expected)
+            .sum();
+        assert!(chi2 < 20.5, "chi-squared {}", chi2);
+    }
+}
@@ -0,0 +1,118 @@
+//!
+//! A random generator built from a block cipher in counter mode.
+
+use alloc::{boxed::Box, vec};
+use zeroize::{Zeroize, Zeroizing};
+
+use super::SecureRandom;
+use crate::symm::{Operation, SymmetricCipher};
+
+///
+/// Produces the keystream of `C` in counter mode: the encryptions of a big-endian block counter starting at zero, under a key taken from the seed.
+///
+/// Seeding with [`SecureRandom::seed`] fills the key from the little-endian bytes of the seed words and restarts the counter, so the same seed always gives the same stream.
+/// Any unused bytes of the last block of a request are discarded. The key is not changed between requests, so anyone who learns the key can reproduce the whole stream, both before and after that point.
+pub struct CtrRand<C: SymmetricCipher + Zeroize> {
+    cipher: Zeroizing<C>,
+    counter: Zeroizing<Box<[u8]>>,
+}
+
+impl<C: SymmetricCipher + Zeroize> CtrRand<C> {
+    ///
+    /// Creates a generator from `cipher`, keyed with all zero bytes until it is seeded
+    pub fn new(cipher: C) -> Self {
+        let mut ret = Self {
+            cipher: Zeroizing::new(cipher),
+            counter: Zeroizing::new(vec![0u8; C::BLOCK_SIZE].into_boxed_slice()),
+        };
+        ret.seed(core::iter::empty());
+        ret
+    }
+
+    fn next_block(&mut self, out: &mut [u8]) {
+        self.cipher.update(&self.counter, out);
+        for b in self.counter.iter_mut().rev() {
+            let (v, overflow) = b.overflowing_add(1);
+            *b = v;
+            if !overflow {
+                break;
+            }
+        }
+    }
+}
+
+impl<C: SymmetricCipher + Zeroize> SecureRandom for CtrRand<C> {
+    const STATE_SIZE: usize = C::KEY_SIZE;
+
+    fn seed<I: IntoIterator<Item = u64>>(&mut self, seed: I) {
+        let mut key = Zeroizing::new(vec![0u8; C::KEY_SIZE]);
+        for (o, v) in key.chunks_mut(8).zip(seed) {
+            let bytes = v.to_le_bytes();
+            let len = o.len();
+            o.copy_from_slice(&bytes[..len])
+        }
+        self.cipher.init(&key, Operation::Encrypt);
+        self.counter.fill(0);
+    }
+
+    fn next_bytes(&mut self, out: &mut [u8]) {
+        let mut r = out.chunks_mut(C::BLOCK_SIZE);
+        let last = r.next_back().unwrap_or(&mut []);
+        for block in r {
+            self.next_block(block);
+        }
+        if last.is_empty() {
+            return;
+        }
+        let mut fblock = Zeroizing::new(vec![0u8; C::BLOCK_SIZE]);
+        self.next_block(&mut fblock);
+        let len = last.len();
+        last.copy_from_slice(&fblock[..len]);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CtrRand;
+    use crate::{
+        rand::SecureRandom,
+        symm::{aes::Aes, Operation, SymmetricCipher},
+    };
+
+    #[test]
+    pub fn ctr_rand_matches_counter_encryption() {
+        let mut rand = CtrRand::new(Aes::<128>::const_new());
+        rand.seed([0x0706050403020100, 0x0f0e0d0c0b0a0908]);
+        let mut out = [0u8; 40];
+        rand.next_bytes(&mut out);
+        let mut next = [0u8; 16];
+        rand.next_bytes(&mut next);
+
+        let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let mut aes = Aes::<128>::const_new();
+        aes.init(&key, Operation::Encrypt);
+        let mut expected = [0u8; 64];
+        for (i, block) in expected.chunks_mut(16).enumerate() {
+            let counter = (i as u128).to_be_bytes();
+            aes.update(&counter, block);
+        }
+        assert_eq!(out, expected[..40]);
+        // The rest of the third block was discarded
+        assert_eq!(next, expected[48..]);
+    }
+
+    #[test]
+    pub fn ctr_rand_reseed() {
+        let mut rand = CtrRand::new(Aes::<256>::const_new());
+        rand.seed([1, 2, 3, 4]);
+        let mut a = [0u8; 32];
+        rand.next_bytes(&mut a);
+        rand.seed([1, 2, 3, 5]);
+        let mut b = [0u8; 32];
+        rand.next_bytes(&mut b);
+        assert_ne!(a, b);
+        rand.seed([1, 2, 3, 4]);
+        rand.next_bytes(&mut b);
+        assert_eq!(a, b);
+    }
+}
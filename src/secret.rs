@@ -0,0 +1,449 @@
+//!
+//! Types for holding and operating on secret values.
+//!
+//! Operations in this module do not branch on, or index memory by, the secret values they operate on.
+
+use alloc::{vec, vec::Vec};
+use zeroize::{Zeroize, Zeroizing};
+
+pub mod base32;
+
+fn mask(choice: bool) -> u8 {
+    crate::asm::ct_eq_mask(choice as usize, 1) as u8
+}
+
+///
+/// A fixed-width unsigned integer of `N` bytes, stored big-endian and zeroed on drop.
+///
+/// All arithmetic is constant-time in the values (but not in `N`). Modular operations require their operands to already be reduced (less than the modulus);
+///  the result is unspecified otherwise.
+#[derive(Clone)]
+pub struct SecretUint<const N: usize>(Zeroizing<[u8; N]>);
+
+impl<const N: usize> SecretUint<N> {
+    pub fn zero() -> Self {
+        Self(Zeroizing::new([0; N]))
+    }
+
+    pub fn from_be_bytes(bytes: [u8; N]) -> Self {
+        Self(Zeroizing::new(bytes))
+    }
+
+    pub fn as_be_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+
+    ///
+    /// Returns `b` if `choice` is true, and `a` otherwise, without branching on `choice`
+    pub fn conditional_select(a: &Self, b: &Self, choice: bool) -> Self {
+        let mut ret = a.clone();
+        ret.conditional_assign(b, choice);
+        ret
+    }
+
+    ///
+    /// Sets `self` to `other` if `choice` is true, without branching on `choice`
+    pub fn conditional_assign(&mut self, other: &Self, choice: bool) {
+        crate::mem::conditional_copy(&mut *self.0, &*other.0, choice);
+    }
+
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        crate::cmp::eq(&*self.0, &*other.0)
+    }
+
+    ///
+    /// Returns whether `self<other`, without branching on either value
+    pub fn ct_lt(&self, other: &Self) -> bool {
+        let mut tmp = self.clone();
+        tmp.sub_assign_borrow(other) != 0
+    }
+
+    ///
+    /// Adds `other` to `self` modulo `2^(8N)`, returning the carry out (0 or 1)
+    fn add_assign_carry(&mut self, other: &Self) -> u8 {
+        let mut carry = 0u16;
+        for (a, b) in self.0.iter_mut().rev().zip(other.0.iter().rev()) {
+            let sum = u16::from(*a) + u16::from(*b) + carry;
+            *a = sum as u8;
+            carry = sum >> 8;
+        }
+        carry as u8
+    }
+
+    ///
+    /// Subtracts `other` from `self` modulo `2^(8N)`, returning the borrow out (0 or 1)
+    fn sub_assign_borrow(&mut self, other: &Self) -> u8 {
+        let mut borrow = 0u16;
+        for (a, b) in self.0.iter_mut().rev().zip(other.0.iter().rev()) {
+            let diff = u16::from(*a)
+                .wrapping_sub(u16::from(*b))
+                .wrapping_sub(borrow);
+            *a = diff as u8;
+            borrow = (diff >> 8) & 1;
+        }
+        borrow as u8
+    }
+
+    ///
+    /// Computes `(self+other) mod modulus`
+    pub fn add_mod(&self, other: &Self, modulus: &Self) -> Self {
+        let mut sum = self.clone();
+        let carry = sum.add_assign_carry(other);
+        let mut reduced = sum.clone();
+        let borrow = reduced.sub_assign_borrow(modulus);
+        // The true sum is at least the modulus iff it overflowed N bytes, or subtracting the modulus did not borrow
+        sum.conditional_assign(&reduced, (carry | (borrow ^ 1)) != 0);
+        sum
+    }
+
+    ///
+    /// Computes `(self-other) mod modulus`
+    pub fn sub_mod(&self, other: &Self, modulus: &Self) -> Self {
+        let mut diff = self.clone();
+        let borrow = diff.sub_assign_borrow(other);
+        let mut correction = modulus.clone();
+        let m = mask(borrow != 0);
+        for b in correction.0.iter_mut() {
+            *b &= m;
+        }
+        diff.add_assign_carry(&correction);
+        diff
+    }
+
+    ///
+    /// Computes `(-self) mod modulus`, which is zero if `self` is zero
+    pub fn neg_mod(&self, modulus: &Self) -> Self {
+        Self::zero().sub_mod(self, modulus)
+    }
+
+    ///
+    /// Computes `(self*other) mod modulus`.
+    ///
+    /// This is a double-and-add over the bits of `other`, performing the same `16N` modular additions regardless of the operands.
+    pub fn mul_mod(&self, other: &Self, modulus: &Self) -> Self {
+        let mut acc = Self::zero();
+        for byte in other.0.iter() {
+            for bit in (0..8).rev() {
+                acc = acc.add_mod(&acc, modulus);
+                let added = acc.add_mod(self, modulus);
+                acc.conditional_assign(&added, (byte >> bit) & 1 != 0);
+            }
+        }
+        acc
+    }
+}
+
+impl<const N: usize> Zeroize for SecretUint<N> {
+    fn zeroize(&mut self) {
+        self.0.zeroize()
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for SecretUint<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("SecretUint(<redacted>)")
+    }
+}
+
+///
+/// Sets `out` to `a*b` modulo `256^out.len()`, with all three little-endian
+fn mul_le(a: &[u8], b: &[u8], out: &mut [u8]) {
+    out.fill(0);
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u32;
+        for (j, &y) in b.iter().enumerate() {
+            if let Some(o) = out.get_mut(i + j) {
+                let t = u32::from(*o) + u32::from(x) * u32::from(y) + carry;
+                *o = t as u8;
+                carry = t >> 8;
+            }
+        }
+        for o in out.iter_mut().skip(i + b.len()) {
+            let t = u32::from(*o) + carry;
+            *o = t as u8;
+            carry = t >> 8;
+        }
+    }
+}
+
+///
+/// Subtracts `b` from `a` modulo `256^a.len()`, with both little-endian and `b` zero-extended, returning the borrow out (0 or 1)
+fn sub_le(a: &mut [u8], b: &[u8]) -> u8 {
+    let mut borrow = 0u16;
+    for (i, x) in a.iter_mut().enumerate() {
+        let y = b.get(i).copied().unwrap_or(0);
+        let diff = u16::from(*x)
+            .wrapping_sub(u16::from(y))
+            .wrapping_sub(borrow);
+        *x = diff as u8;
+        borrow = (diff >> 8) & 1;
+    }
+    borrow as u8
+}
+
+///
+/// Subtracts `m` from `r` if `r>=m`, without branching on either value
+fn conditional_sub_le(r: &mut [u8], m: &[u8]) {
+    let mut diff = Zeroizing::new(r.to_vec());
+    let borrow = sub_le(&mut diff, m);
+    crate::mem::conditional_copy(r, &diff, borrow == 0);
+}
+
+///
+/// Barrett reduction modulo a fixed `N`-byte modulus, with the constant `mu = floor(2^(16N)/modulus)` precomputed.
+///
+/// Reducing a `2N`-byte value then takes two multiplications and at most two conditional subtractions, all constant-time, rather than a division or `16N` modular additions.
+/// The modulus must have a nonzero most significant byte, and may itself be secret: the precomputation is a constant-time long division.
+pub struct BarrettCtx<const N: usize> {
+    modulus: SecretUint<N>,
+    // Both little-endian, N+1 and N+2 bytes (mu reaches 256^(N+1) when the modulus is 256^(N-1))
+    modulus_le: Zeroizing<Vec<u8>>,
+    mu: Zeroizing<Vec<u8>>,
+}
+
+impl<const N: usize> BarrettCtx<N> {
+    ///
+    /// Precomputes `mu` for `modulus`.
+    ///
+    /// Panics if the most significant byte of `modulus` is zero.
+    pub fn new(modulus: &SecretUint<N>) -> Self {
+        assert!(
+            N > 0 && modulus.0[0] != 0,
+            "Barrett modulus must have a nonzero top byte"
+        );
+        let mut modulus_le = Zeroizing::new(vec![0u8; N + 1]);
+        for (o, &b) in modulus_le.iter_mut().zip(modulus.0.iter().rev()) {
+            *o = b;
+        }
+        // Long division of 2^(16N) by the modulus, one bit at a time. The remainder stays below the modulus, and the quotient is at most 2^(8N+8)
+        let mut rem = Zeroizing::new(vec![0u8; N + 1]);
+        let mut mu = Zeroizing::new(vec![0u8; N + 2]);
+        for bit in (0..=16 * N).rev() {
+            let mut carry = u8::from(bit == 16 * N);
+            for b in rem.iter_mut() {
+                let next = *b >> 7;
+                *b = (*b << 1) | carry;
+                carry = next;
+            }
+            let mut diff = Zeroizing::new(rem.to_vec());
+            let ge = sub_le(&mut diff, &modulus_le) == 0;
+            crate::mem::conditional_copy(&mut rem, &diff, ge);
+            if bit < 8 * (N + 2) {
+                mu[bit / 8] |= u8::from(ge) << (bit % 8);
+            }
+        }
+        Self {
+            modulus: modulus.clone(),
+            modulus_le,
+            mu,
+        }
+    }
+
+    ///
+    /// The modulus this context reduces by.
+    pub fn modulus(&self) -> &SecretUint<N> {
+        &self.modulus
+    }
+
+    ///
+    /// Computes `x mod modulus` for a `W`-byte `x`.
+    ///
+    /// Panics if `W!=2N`.
+    pub fn reduce<const W: usize>(&self, x: &SecretUint<W>) -> SecretUint<N> {
+        assert_eq!(
+            W,
+            2 * N,
+            "Barrett reduction input must be twice the modulus width"
+        );
+        let mut x_le = Zeroizing::new(x.0.to_vec());
+        x_le.reverse();
+        self.reduce_le(&x_le)
+    }
+
+    ///
+    /// Computes `(a*b) mod modulus`, as a full product followed by [`BarrettCtx::reduce`]. Both operands must be reduced.
+    pub fn mul_mod(&self, a: &SecretUint<N>, b: &SecretUint<N>) -> SecretUint<N> {
+        let mut a_le = Zeroizing::new(a.0.to_vec());
+        let mut b_le = Zeroizing::new(b.0.to_vec());
+        a_le.reverse();
+        b_le.reverse();
+        let mut product = Zeroizing::new(vec![0u8; 2 * N]);
+        mul_le(&a_le, &b_le, &mut product);
+        self.reduce_le(&product)
+    }
+
+    ///
+    /// Reduces the `2N`-byte little-endian `x`
+    fn reduce_le(&self, x: &[u8]) -> SecretUint<N> {
+        // q3 = floor(floor(x/256^(N-1)) * mu / 256^(N+1)), which is at most 2 less than floor(x/modulus)
+        let mut q2 = Zeroizing::new(vec![0u8; 2 * N + 3]);
+        mul_le(&x[N - 1..], &self.mu, &mut q2);
+        // r = x - q3*modulus, computed modulo 256^(N+1) where it fits
+        let mut r2 = Zeroizing::new(vec![0u8; N + 1]);
+        mul_le(&q2[N + 1..], &self.modulus_le, &mut r2);
+        let mut r = Zeroizing::new(x[..N + 1].to_vec());
+        sub_le(&mut r, &r2);
+        conditional_sub_le(&mut r, &self.modulus_le);
+        conditional_sub_le(&mut r, &self.modulus_le);
+        let mut out = SecretUint::zero();
+        for (o, &b) in out.0.iter_mut().rev().zip(r.iter()) {
+            *o = b;
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BarrettCtx, SecretUint};
+
+    fn u1(v: u32) -> SecretUint<1> {
+        SecretUint::from_be_bytes([v as u8])
+    }
+
+    fn u2(v: u32) -> SecretUint<2> {
+        SecretUint::from_be_bytes((v as u16).to_be_bytes())
+    }
+
+    fn hex32(s: &str) -> SecretUint<32> {
+        let mut bytes = [0u8; 32];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        SecretUint::from_be_bytes(bytes)
+    }
+
+    #[test]
+    pub fn secret_uint_exhaustive_u8() {
+        let m = 251;
+        let modulus = u1(m);
+        for a in 0..m {
+            for b in 0..m {
+                let (x, y) = (u1(a), u1(b));
+                assert_eq!(x.add_mod(&y, &modulus).as_be_bytes()[0] as u32, (a + b) % m);
+                assert_eq!(
+                    x.sub_mod(&y, &modulus).as_be_bytes()[0] as u32,
+                    (a + m - b) % m
+                );
+                assert_eq!(x.ct_lt(&y), a < b);
+                assert_eq!(x.ct_eq(&y), a == b);
+            }
+        }
+        for (a, b) in [(0, 0), (1, 250), (250, 250), (17, 42), (128, 200)] {
+            assert_eq!(
+                u1(a).mul_mod(&u1(b), &modulus).as_be_bytes()[0] as u32,
+                a * b % m
+            );
+        }
+    }
+
+    #[test]
+    pub fn secret_uint_u16_high_modulus() {
+        // A modulus close to 2^16 makes the sum overflow the width
+        let m = 65521;
+        let modulus = u2(m);
+        let mut a = 12345u32;
+        for _ in 0..200 {
+            let b = (a * 7919 + 13) % m;
+            let (x, y) = (u2(a), u2(b));
+            let got = |v: SecretUint<2>| u16::from_be_bytes(*v.as_be_bytes()) as u32;
+            assert_eq!(got(x.add_mod(&y, &modulus)), (a + b) % m);
+            assert_eq!(got(x.sub_mod(&y, &modulus)), (a + m - b) % m);
+            assert_eq!(got(x.mul_mod(&y, &modulus)), a * b % m);
+            a = (a * 31 + 7) % m;
+        }
+    }
+
+    #[test]
+    pub fn secret_uint_p25519() {
+        // Expected values computed with Python integers
+        let p = hex32("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed");
+        let a = hex32("5a1b2c3d4e5f60718293a4b5c6d7e8f90123456789abcdef0fedcba987654321");
+        let b = hex32("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffea");
+        let sum = hex32("5a1b2c3d4e5f60718293a4b5c6d7e8f90123456789abcdef0fedcba98765431e");
+        let diff = hex32("5a1b2c3d4e5f60718293a4b5c6d7e8f90123456789abcdef0fedcba987654324");
+        let prod = hex32("71ae7b4814e1deab784511deab784514fc962fc962fc9632d0369d0369d03664");
+        assert!(a.add_mod(&b, &p).ct_eq(&sum));
+        assert!(a.sub_mod(&b, &p).ct_eq(&diff));
+        assert!(a.mul_mod(&b, &p).ct_eq(&prod));
+    }
+
+    #[test]
+    pub fn secret_uint_conditional_select() {
+        let a = u2(0x1234);
+        let b = u2(0xabcd);
+        assert!(SecretUint::conditional_select(&a, &b, false).ct_eq(&a));
+        assert!(SecretUint::conditional_select(&a, &b, true).ct_eq(&b));
+    }
+
+    #[test]
+    pub fn secret_uint_p25519_wraparound() {
+        let p = hex32("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed");
+        let p_minus_1 = hex32("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffec");
+        let p_minus_2 = hex32("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeb");
+        let zero = SecretUint::<32>::zero();
+        let one = hex32("0000000000000000000000000000000000000000000000000000000000000001");
+        let two = hex32("0000000000000000000000000000000000000000000000000000000000000002");
+
+        assert!(p_minus_1.add_mod(&one, &p).ct_eq(&zero));
+        assert!(p_minus_1.add_mod(&two, &p).ct_eq(&one));
+        assert!(p_minus_1.add_mod(&p_minus_1, &p).ct_eq(&p_minus_2));
+        assert!(zero.sub_mod(&one, &p).ct_eq(&p_minus_1));
+        assert!(one.sub_mod(&p_minus_1, &p).ct_eq(&two));
+        assert!(one.neg_mod(&p).ct_eq(&p_minus_1));
+        assert!(p_minus_1.neg_mod(&p).ct_eq(&one));
+        assert!(zero.neg_mod(&p).ct_eq(&zero));
+        let a = hex32("5a1b2c3d4e5f60718293a4b5c6d7e8f90123456789abcdef0fedcba987654321");
+        assert!(a.add_mod(&a.neg_mod(&p), &p).ct_eq(&zero));
+    }
+
+    #[test]
+    pub fn barrett_u16() {
+        // Every 4-byte input is reduced as u64 arithmetic would
+        for m in [0x0100u32, 0x8000, 65521, 0xffff] {
+            let ctx = BarrettCtx::new(&u2(m));
+            let mut x = 0x1234_5678u32;
+            for _ in 0..2000 {
+                let r = ctx.reduce(&SecretUint::from_be_bytes(x.to_be_bytes()));
+                assert_eq!(u16::from_be_bytes(*r.as_be_bytes()) as u32, x % m);
+                x = x.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            }
+            for x in [0u32, m - 1, m, u32::MAX] {
+                let r = ctx.reduce(&SecretUint::from_be_bytes(x.to_be_bytes()));
+                assert_eq!(u16::from_be_bytes(*r.as_be_bytes()) as u32, x % m);
+            }
+        }
+    }
+
+    #[test]
+    pub fn barrett_mul_mod_matches() {
+        let p25519 = hex32("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed");
+        let p256 = hex32("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff");
+        let mut seed = *hex32("5a1b2c3d4e5f60718293a4b5c6d7e8f90123456789abcdef0fedcba987654321")
+            .as_be_bytes();
+        for p in [p25519, p256] {
+            let ctx = BarrettCtx::new(&p);
+            for _ in 0..16 {
+                // Operands from a simple byte-rotating sequence, reduced below p by clearing the top bit
+                seed.rotate_left(5);
+                seed[7] = seed[7].wrapping_add(0x3b);
+                let mut a = seed;
+                a[0] &= 0x7f;
+                let mut b = seed;
+                b.reverse();
+                b[0] &= 0x7f;
+                let (a, b) = (SecretUint::from_be_bytes(a), SecretUint::from_be_bytes(b));
+                assert!(ctx.mul_mod(&a, &b).ct_eq(&a.mul_mod(&b, &p)));
+            }
+            let p_minus_1 = p.sub_mod(&hex32(&format!("{:064x}", 1)), &p);
+            let one = hex32(&format!("{:064x}", 1));
+            assert!(ctx.mul_mod(&p_minus_1, &p_minus_1).ct_eq(&one));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn barrett_rejects_short_modulus() {
+        BarrettCtx::new(&u2(0xff));
+    }
+}
@@ -0,0 +1,189 @@
+//!
+//! Base32 encoding of RFC 4648, as used for TOTP and HOTP secrets.
+//!
+//! Neither encoding nor decoding indexes a table by, or branches on, the encoded bytes, so secrets can be parsed without leaking them through timing.
+//! Only the length of the input, and whether it was valid, is observable.
+
+use alloc::{string::String, vec::Vec};
+use zeroize::Zeroizing;
+
+use crate::error::{Error, ErrorKind, Result};
+
+///
+/// Returns `0xff` if `lo <= c <= hi`, and `0` otherwise, without branching on `c`
+fn range_mask(c: u8, lo: u8, hi: u8) -> u8 {
+    let below = i16::from(c) - i16::from(lo);
+    let above = i16::from(hi) - i16::from(c);
+    !((below | above) >> 15) as u8
+}
+
+fn encode_symbol(v: u8) -> char {
+    // 'A'..='Z' for 0..26, then '2'..='7'
+    let digit = range_mask(v, 26, 31);
+    let c = v
+        .wrapping_add(b'A')
+        .wrapping_add(digit & b'2'.wrapping_sub(b'A' + 26));
+    char::from(c)
+}
+
+///
+/// Returns the value of a base32 symbol (accepting lowercase letters), and a mask which is `0xff` if `c` is a valid symbol
+fn decode_symbol(c: u8) -> (u8, u8) {
+    let upper = range_mask(c, b'A', b'Z');
+    let lower = range_mask(c, b'a', b'z');
+    let digit = range_mask(c, b'2', b'7');
+    let v = (upper & c.wrapping_sub(b'A'))
+        | (lower & c.wrapping_sub(b'a'))
+        | (digit & c.wrapping_sub(b'2' - 26));
+    (v, upper | lower | digit)
+}
+
+fn encode_inner(bytes: &[u8], pad: bool) -> Zeroizing<String> {
+    let mut out = Zeroizing::new(String::with_capacity(bytes.len().div_ceil(5) * 8));
+    let mut acc = 0u16;
+    let mut bits = 0;
+    for b in bytes {
+        acc = (acc << 8) | u16::from(*b);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(encode_symbol(((acc >> bits) & 0x1f) as u8));
+        }
+    }
+    if bits > 0 {
+        out.push(encode_symbol(((acc << (5 - bits)) & 0x1f) as u8));
+    }
+    if pad {
+        while out.len() % 8 != 0 {
+            out.push('=');
+        }
+    }
+    out
+}
+
+///
+/// Encodes `bytes` as padded base32
+///
+/// ## Examples
+///
+/// ```
+/// use lc_crypto::secret::base32;
+/// assert_eq!(&*base32::encode(b"foob"), "MZXW6YQ=");
+/// ```
+pub fn encode(bytes: &[u8]) -> Zeroizing<String> {
+    encode_inner(bytes, true)
+}
+
+///
+/// Encodes `bytes` as base32 without `=` padding, the form usually used for TOTP secrets
+pub fn encode_unpadded(bytes: &[u8]) -> Zeroizing<String> {
+    encode_inner(bytes, false)
+}
+
+///
+/// Decodes base32 text, with or without `=` padding. Lowercase letters are accepted.
+///
+/// Returns an error of kind [`ErrorKind::InvalidData`] if `text` contains a character outside the base32 alphabet,
+///  has a length that no encoding produces, or has padding anywhere other than the end.
+/// Every character is examined before an invalid one is reported.
+pub fn decode(text: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let invalid = || Error::new_with_message(ErrorKind::InvalidData, "invalid base32");
+    let text = text.as_bytes();
+    let unpadded = text.len() - text.iter().rev().take_while(|&&c| c == b'=').count();
+    if unpadded != text.len() && text.len() % 8 != 0 {
+        return Err(invalid());
+    }
+    let body = &text[..unpadded];
+    if matches!(body.len() % 8, 1 | 3 | 6) || text.len() - unpadded >= 8 {
+        return Err(invalid());
+    }
+
+    let mut out = Zeroizing::new(Vec::with_capacity(body.len() * 5 / 8));
+    let mut valid = 0xffu8;
+    let mut acc = 0u16;
+    let mut bits = 0;
+    for c in body {
+        let (v, ok) = decode_symbol(*c);
+        valid &= ok;
+        acc = (acc << 5) | u16::from(v);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if valid != 0xff {
+        return Err(invalid());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, encode, encode_unpadded};
+    use crate::error::ErrorKind;
+
+    const RFC4648: [(&str, &str); 7] = [
+        ("", ""),
+        ("f", "MY======"),
+        ("fo", "MZXQ===="),
+        ("foo", "MZXW6==="),
+        ("foob", "MZXW6YQ="),
+        ("fooba", "MZXW6YTB"),
+        ("foobar", "MZXW6YTBOI======"),
+    ];
+
+    #[test]
+    pub fn base32_rfc4648() {
+        for (plain, encoded) in RFC4648 {
+            assert_eq!(&*encode(plain.as_bytes()), encoded);
+            assert_eq!(
+                &*encode_unpadded(plain.as_bytes()),
+                encoded.trim_end_matches('=')
+            );
+            assert_eq!(&**decode(encoded).unwrap(), plain.as_bytes());
+            assert_eq!(
+                &**decode(encoded.trim_end_matches('=')).unwrap(),
+                plain.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    pub fn base32_totp_secrets() {
+        assert_eq!(
+            &**decode("JBSWY3DPEHPK3PXP").unwrap(),
+            b"Hello!\xde\xad\xbe\xef"
+        );
+        // The RFC 6238 SHA-1 test key
+        assert_eq!(
+            &**decode("gezdgnbvgy3tqojqgezdgnbvgy3tqojq").unwrap(),
+            b"12345678901234567890"
+        );
+    }
+
+    #[test]
+    pub fn base32_round_trip() {
+        let bytes: alloc::vec::Vec<u8> = (0..=255u8).collect();
+        for len in 0..40 {
+            assert_eq!(&**decode(&encode(&bytes[..len])).unwrap(), &bytes[..len]);
+            let shifted = &bytes[216..216 + len];
+            assert_eq!(&**decode(&encode_unpadded(shifted)).unwrap(), shifted);
+        }
+    }
+
+    #[test]
+    pub fn base32_invalid() {
+        for text in [
+            "MZXW6YQ1", "MZXW6YQ8", "MZXW6YQ@", "MZXW 6YQ", "MZX", "M=======", "MZXQ==",
+            "MZ=XQ===", "========",
+        ] {
+            assert_eq!(
+                decode(text).unwrap_err().kind(),
+                ErrorKind::InvalidData,
+                "{}",
+                text
+            );
+        }
+    }
+}
@@ -7,7 +7,21 @@ use alloc::{borrow::Cow, boxed::Box, vec, vec::Vec};
 
 use zeroize::{Zeroize, Zeroizing};
 
+use crate::{
+    asm::{ct_eq_mask, ct_lt_mask},
+    digest::{Digest, Hmac},
+    error::{Error, ErrorKind, Result},
+};
+
+pub mod aead;
 pub mod aes;
+pub mod aes_gcm_siv;
+pub mod aes_siv;
+pub mod arx;
+pub mod chacha20;
+pub mod chacha20poly1305;
+#[cfg(any(test, feature = "std"))]
+pub mod stream;
 
 #[derive(Copy, Clone)]
 pub enum Operation {
@@ -282,3 +296,280 @@ pub fn decrypt<C: SymmetricCipher>(mut cipher: C, key: &[u8], input: &[u8]) -> V
 
     out
 }
+
+///
+/// Encrypts `input` with the MAC-then-encrypt construction used by the CBC cipher suites of TLS 1.0 through 1.2.
+///
+/// An HMAC-`D` tag over `aad || input` is computed under `mac_key`, then `input || tag || padding` is encrypted with `cipher` in CBC mode under `key` and `iv`.
+/// The padding is `p+1` bytes of value `p`, for the smallest `p` that fills the last block.
+///
+/// This construction exists for interoperability. New protocols should use an AEAD instead.
+///
+/// Returns an error of kind [`ErrorKind::InvalidInput`] if `iv` is not `C::BLOCK_SIZE` bytes.
+pub fn mac_then_encrypt<C: SymmetricCipher, D: Digest + Default>(
+    cipher: C,
+    key: &[u8],
+    iv: &[u8],
+    mac_key: &[u8],
+    aad: &[u8],
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    if iv.len() != C::BLOCK_SIZE {
+        return Err(Error::new_with_message(
+            ErrorKind::InvalidInput,
+            "IV must be one cipher block",
+        ));
+    }
+    let mut msg = Zeroizing::new(Vec::with_capacity(aad.len() + input.len()));
+    msg.extend_from_slice(aad);
+    msg.extend_from_slice(input);
+    let mut tag = Zeroizing::new(vec![0u8; D::OUTPUT_SIZE].into_boxed_slice());
+    crate::digest::digest(Hmac::new(D::default(), mac_key), &msg, &mut tag);
+
+    let mut plain = Zeroizing::new(Vec::with_capacity(input.len() + tag.len() + C::BLOCK_SIZE));
+    plain.extend_from_slice(input);
+    plain.extend_from_slice(&tag);
+    let p = C::BLOCK_SIZE - 1 - plain.len() % C::BLOCK_SIZE;
+    let len = plain.len();
+    plain.resize(len + p + 1, p as u8);
+    Ok(encrypt(CBC::new(cipher, iv.into()), key, &plain))
+}
+
+///
+/// Decrypts and verifies `input` produced by [`mac_then_encrypt`].
+///
+/// The padding check, the MAC computation, and the extraction of the received tag are all done without branching on the padding length,
+///  and the HMAC performs the same number of compression function calls for every padding length,
+///  so that neither the padding nor the MAC result can be recovered through timing (as in the Lucky Thirteen attack).
+/// The compression count assumes a Merkle-Damgard digest with a `BLOCK_SIZE/8` byte length field, such as SHA-1 or SHA-2.
+///
+/// Returns an error of kind [`ErrorKind::AuthenticationFailed`] if the padding or the tag are invalid, without distinguishing between the two,
+///  and [`ErrorKind::InvalidInput`] if the length of `input` cannot be a valid ciphertext or `iv` is not `C::BLOCK_SIZE` bytes.
+pub fn mac_then_decrypt<C: SymmetricCipher, D: Digest + Default>(
+    cipher: C,
+    key: &[u8],
+    iv: &[u8],
+    mac_key: &[u8],
+    aad: &[u8],
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    let tag_len = D::OUTPUT_SIZE;
+    if input.is_empty() || input.len() % C::BLOCK_SIZE != 0 || input.len() <= tag_len {
+        return Err(Error::new_with_message(
+            ErrorKind::InvalidInput,
+            "ciphertext length is invalid for the cipher and MAC",
+        ));
+    }
+    if iv.len() != C::BLOCK_SIZE {
+        return Err(Error::new_with_message(
+            ErrorKind::InvalidInput,
+            "IV must be one cipher block",
+        ));
+    }
+    let plain = Zeroizing::new(decrypt(CBC::new(cipher, iv.into()), key, input));
+    let len = plain.len();
+
+    // The padding is p+1 bytes of value p, and must leave room for the tag
+    let p = plain[len - 1] as usize;
+    let max_pad = (len - tag_len).min(256);
+    let mut good = ct_lt_mask(p, max_pad);
+    for i in 1..=max_pad {
+        let in_pad = ct_lt_mask(i - 1, p + 1);
+        good &= !(in_pad & !ct_eq_mask(plain[len - i] as usize, p));
+    }
+    // On bad padding, continue as though there were none, so the timing is the same
+    let content_len = len - tag_len - ((p & good) + 1);
+
+    let mut msg = Zeroizing::new(Vec::with_capacity(aad.len() + len));
+    msg.extend_from_slice(aad);
+    msg.extend_from_slice(&plain);
+    let mut computed = Zeroizing::new(vec![0u8; tag_len].into_boxed_slice());
+    crate::digest::digest(
+        Hmac::new(D::default(), mac_key),
+        &msg[..(aad.len() + content_len)],
+        &mut computed,
+    );
+
+    // Pad out to the number of compression calls needed for the longest possible content
+    let calls = |len: usize| (len + D::BLOCK_SIZE / 8 + D::BLOCK_SIZE) / D::BLOCK_SIZE;
+    let extra = calls(aad.len() + len - tag_len - 1) - calls(aad.len() + content_len);
+    let mut dummy = D::default();
+    dummy.init();
+    let block = vec![0u8; D::BLOCK_SIZE];
+    for _ in 0..extra {
+        dummy.update(&block);
+    }
+    // The state is never read, so without this the compiler could remove the extra calls
+    core::hint::black_box(&mut dummy);
+
+    let mut received = Zeroizing::new(vec![0u8; tag_len].into_boxed_slice());
+    for off in (len - tag_len - max_pad)..(len - tag_len) {
+        let mask = ct_eq_mask(off, content_len) as u8;
+        for (a, &b) in received.iter_mut().zip(&plain[off..]) {
+            *a |= b & mask;
+        }
+    }
+
+    if crate::cmp::eq(&computed, &received) & (good != 0) {
+        Ok(plain[..content_len].to_vec())
+    } else {
+        Err(ErrorKind::AuthenticationFailed.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec::Vec;
+
+    use crate::{
+        digest::{sha2::Sha256, Hmac},
+        error::ErrorKind,
+        symm::{aes::Aes, CBC},
+    };
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f,
+        0x3c,
+    ];
+    const IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+    const MAC_KEY: &[u8] = b"mac key";
+    const AAD: &[u8] = b"header";
+
+    #[test]
+    fn mac_then_decrypt_round_trip() {
+        let input: Vec<u8> = (0..100u8).collect();
+        for len in 0..input.len() {
+            let ct = super::mac_then_encrypt::<_, Sha256>(
+                Aes::<128>::const_new(),
+                &KEY,
+                &IV,
+                MAC_KEY,
+                AAD,
+                &input[..len],
+            )
+            .unwrap();
+            assert_eq!(ct.len() % 16, 0);
+            let pt = super::mac_then_decrypt::<_, Sha256>(
+                Aes::<128>::const_new(),
+                &KEY,
+                &IV,
+                MAC_KEY,
+                AAD,
+                &ct,
+            )
+            .unwrap();
+            assert_eq!(pt, &input[..len]);
+        }
+    }
+
+    #[test]
+    fn mac_then_decrypt_tampered() {
+        let input = b"The quick brown fox jumps over the lazy dog";
+        let ct = super::mac_then_encrypt::<_, Sha256>(
+            Aes::<128>::const_new(),
+            &KEY,
+            &IV,
+            MAC_KEY,
+            AAD,
+            input,
+        )
+        .unwrap();
+        for i in 0..ct.len() {
+            let mut tampered = ct.clone();
+            tampered[i] ^= 0x01;
+            let err = super::mac_then_decrypt::<_, Sha256>(
+                Aes::<128>::const_new(),
+                &KEY,
+                &IV,
+                MAC_KEY,
+                AAD,
+                &tampered,
+            )
+            .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::AuthenticationFailed);
+        }
+        let err = super::mac_then_decrypt::<_, Sha256>(
+            Aes::<128>::const_new(),
+            &KEY,
+            &IV,
+            MAC_KEY,
+            b"other header",
+            &ct,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AuthenticationFailed);
+    }
+
+    #[test]
+    fn mac_then_decrypt_bad_padding() {
+        // A correct tag followed by padding bytes which disagree with the final length byte
+        let input = b"0123456789abcdef";
+        let mut tag = [0u8; 32];
+        let mut msg = AAD.to_vec();
+        msg.extend_from_slice(input);
+        crate::digest::digest(Hmac::new(Sha256::new(), MAC_KEY), &msg, &mut tag);
+        let mut plain = input.to_vec();
+        plain.extend_from_slice(&tag);
+        plain.extend_from_slice(&[3, 3, 2, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3]);
+        let ct = super::encrypt(
+            CBC::new(Aes::<128>::const_new(), IV[..].into()),
+            &KEY,
+            &plain,
+        );
+        let err = super::mac_then_decrypt::<_, Sha256>(
+            Aes::<128>::const_new(),
+            &KEY,
+            &IV,
+            MAC_KEY,
+            AAD,
+            &ct,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AuthenticationFailed);
+    }
+
+    #[test]
+    fn mac_then_decrypt_invalid_length() {
+        for len in [0, 15, 17, 32] {
+            let err = super::mac_then_decrypt::<_, Sha256>(
+                Aes::<128>::const_new(),
+                &KEY,
+                &IV,
+                MAC_KEY,
+                AAD,
+                &alloc::vec![0u8; len],
+            )
+            .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn mac_then_encrypt_bad_iv() {
+        for iv in [&IV[..15], &[0u8; 17][..], &[]] {
+            let err = super::mac_then_encrypt::<_, Sha256>(
+                Aes::<128>::const_new(),
+                &KEY,
+                iv,
+                MAC_KEY,
+                AAD,
+                b"message",
+            )
+            .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+            let err = super::mac_then_decrypt::<_, Sha256>(
+                Aes::<128>::const_new(),
+                &KEY,
+                iv,
+                MAC_KEY,
+                AAD,
+                &[0u8; 64],
+            )
+            .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+    }
+}
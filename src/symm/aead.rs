@@ -0,0 +1,402 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+    error::{Error, ErrorKind, Result},
+    rand::SecureRandom,
+};
+
+///
+/// An Authenticated Encryption with Associated Data (AEAD) algorithm.
+///
+/// The key is supplied when constructing the implementation. Each call to [`Aead::seal`] must use a distinct nonce for the same key,
+///  unless the implementation documents that it is resistant to nonce misuse.
+///
+/// Implementations panic if `nonce` is not exactly `NONCE_SIZE` bytes.
+pub trait Aead {
+    const KEY_SIZE: usize;
+    const NONCE_SIZE: usize;
+    const TAG_SIZE: usize;
+
+    ///
+    /// Encrypts `plaintext` and authenticates it together with `aad`, returning the ciphertext followed by the `TAG_SIZE` byte tag (unless the implementation documents a different layout)
+    fn seal(&mut self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8>;
+
+    ///
+    /// Verifies and decrypts the output of [`Aead::seal`].
+    ///
+    /// Returns an error of kind [`crate::error::ErrorKind::AuthenticationFailed`] if `ciphertext` or `aad` were modified, or were sealed under a different key or nonce
+    fn open(&mut self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>>;
+
+    ///
+    /// Takes the next nonce from `nonces` and seals `plaintext` with it, returning the nonce and the output of [`Aead::seal`].
+    ///
+    /// Returns an error if `nonces` cannot issue another nonce, so a nonce is never reused by accident.
+    fn seal_next<S: NonceSource + ?Sized>(
+        &mut self,
+        nonces: &mut S,
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        let mut nonce = alloc::vec![0u8; Self::NONCE_SIZE];
+        nonces.next_nonce(&mut nonce)?;
+        let ct = self.seal(&nonce, aad, plaintext);
+        Ok((nonce, ct))
+    }
+}
+
+///
+/// A source of nonces for [`Aead::seal_next`]
+pub trait NonceSource {
+    ///
+    /// Writes the next nonce to `out`, or returns an error if no more nonces can be issued
+    fn next_nonce(&mut self, out: &mut [u8]) -> Result<()>;
+}
+
+impl<S: NonceSource + ?Sized> NonceSource for &mut S {
+    fn next_nonce(&mut self, out: &mut [u8]) -> Result<()> {
+        <S as NonceSource>::next_nonce(self, out)
+    }
+}
+
+///
+/// Issues `N`-byte nonces as a big-endian counter, so no nonce is ever issued twice.
+///
+/// After the all-ones nonce has been issued, the sequence is exhausted and [`NonceSource::next_nonce`] returns an error of kind [`ErrorKind::Other`], rather than wrapping around.
+/// A key used with a `NonceSequence` should not be used with any other source of nonces.
+pub struct NonceSequence<const N: usize> {
+    next: [u8; N],
+    exhausted: bool,
+}
+
+impl<const N: usize> NonceSequence<N> {
+    ///
+    /// Creates a sequence starting at the all-zeros nonce
+    pub const fn new() -> Self {
+        Self::starting_at([0; N])
+    }
+
+    ///
+    /// Creates a sequence whose first nonce is `nonce`, for example to resume a sequence whose position was saved
+    pub const fn starting_at(nonce: [u8; N]) -> Self {
+        Self {
+            next: nonce,
+            exhausted: false,
+        }
+    }
+}
+
+impl<const N: usize> Default for NonceSequence<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> NonceSource for NonceSequence<N> {
+    fn next_nonce(&mut self, out: &mut [u8]) -> Result<()> {
+        if out.len() != N {
+            return Err(Error::new_with_message(
+                ErrorKind::InvalidInput,
+                "nonce length does not match sequence",
+            ));
+        }
+        if self.exhausted {
+            return Err(Error::new_with_message(
+                ErrorKind::Other,
+                "nonce sequence exhausted",
+            ));
+        }
+        out.copy_from_slice(&self.next);
+        self.exhausted = true;
+        for b in self.next.iter_mut().rev() {
+            let (v, overflow) = b.overflowing_add(1);
+            *b = v;
+            if !overflow {
+                self.exhausted = false;
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+///
+/// Issues nonces drawn from a [`SecureRandom`].
+///
+/// Random nonces are only safe when they are long enough that collisions are negligible, such as the 24-byte nonces of [`super::chacha20poly1305::XChaCha20Poly1305`].
+/// [`NonceSource::next_nonce`] returns an error of kind [`ErrorKind::InvalidInput`] for nonces shorter than 24 bytes.
+pub struct RandomNonce<R> {
+    rand: R,
+}
+
+impl<R: SecureRandom> RandomNonce<R> {
+    pub fn new(rand: R) -> Self {
+        Self { rand }
+    }
+}
+
+impl<R: SecureRandom> NonceSource for RandomNonce<R> {
+    fn next_nonce(&mut self, out: &mut [u8]) -> Result<()> {
+        if out.len() < 24 {
+            return Err(Error::new_with_message(
+                ErrorKind::InvalidInput,
+                "nonce too short to be chosen at random",
+            ));
+        }
+        self.rand.next_bytes(out);
+        Ok(())
+    }
+}
+
+impl<A: Aead + ?Sized> Aead for &mut A {
+    const KEY_SIZE: usize = A::KEY_SIZE;
+    const NONCE_SIZE: usize = A::NONCE_SIZE;
+    const TAG_SIZE: usize = A::TAG_SIZE;
+    fn seal(&mut self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        <A as Aead>::seal(self, nonce, aad, plaintext)
+    }
+    fn open(&mut self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        <A as Aead>::open(self, nonce, aad, ciphertext)
+    }
+}
+
+impl<A: Aead + ?Sized> Aead for Box<A> {
+    const KEY_SIZE: usize = A::KEY_SIZE;
+    const NONCE_SIZE: usize = A::NONCE_SIZE;
+    const TAG_SIZE: usize = A::TAG_SIZE;
+    fn seal(&mut self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        <A as Aead>::seal(self, nonce, aad, plaintext)
+    }
+    fn open(&mut self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        <A as Aead>::open(self, nonce, aad, ciphertext)
+    }
+}
+
+///
+/// An AEAD encryption in progress, for messages or associated data too large to buffer for [`Aead::seal`].
+///
+/// All associated data must be supplied with [`AeadEncryptor::update_aad`] before the first call to [`AeadEncryptor::encrypt_chunk`],
+///  and nothing may be supplied after [`AeadEncryptor::finish`]. Calls out of this order return an error of kind [`crate::error::ErrorKind::InvalidInput`].
+///
+/// Chunks may have any length, and the ciphertext and tag are the same as [`Aead::seal`] produces for the concatenated input.
+pub trait AeadEncryptor {
+    const TAG_SIZE: usize;
+
+    fn update_aad(&mut self, aad: &[u8]) -> Result<()>;
+
+    ///
+    /// Encrypts `plaintext` into `out`, which must be the same length
+    fn encrypt_chunk(&mut self, plaintext: &[u8], out: &mut [u8]) -> Result<()>;
+
+    ///
+    /// Ends the message and returns its `TAG_SIZE` byte tag
+    fn finish(&mut self) -> Result<Vec<u8>>;
+}
+
+///
+/// An object-safe form of [`Aead`], with the key, nonce, and tag sizes available at runtime.
+///
+/// The methods are named apart from those of [`Aead`], so both traits can be imported together.
+///
+/// Every [`Aead`] implements this trait, so a `Box<dyn DynAead>` can hold any AEAD chosen at runtime, for example with [`crate::algorithms::aead`].
+pub trait DynAead {
+    fn dyn_key_size(&self) -> usize;
+
+    fn dyn_nonce_size(&self) -> usize;
+
+    fn dyn_tag_size(&self) -> usize;
+
+    fn dyn_seal(&mut self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8>;
+
+    fn dyn_open(&mut self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>>;
+}
+
+impl<A: Aead> DynAead for A {
+    fn dyn_key_size(&self) -> usize {
+        A::KEY_SIZE
+    }
+
+    fn dyn_nonce_size(&self) -> usize {
+        A::NONCE_SIZE
+    }
+
+    fn dyn_tag_size(&self) -> usize {
+        A::TAG_SIZE
+    }
+
+    fn dyn_seal(&mut self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        <A as Aead>::seal(self, nonce, aad, plaintext)
+    }
+
+    fn dyn_open(&mut self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        <A as Aead>::open(self, nonce, aad, ciphertext)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use alloc::{boxed::Box, vec, vec::Vec};
+
+    use super::{Aead, NonceSequence, NonceSource, RandomNonce};
+    use crate::{
+        digest::{sha2::Sha256, Digest, Hmac},
+        error::{ErrorKind, Result},
+        rand::{DoubleDigestRandom, SecureRandom},
+    };
+
+    ///
+    /// Checks that `aead` round-trips messages of several lengths, and rejects modified ciphertexts, aad, and nonces
+    pub fn round_trip<A: Aead>(mut aead: A) {
+        let nonce = vec![0x24u8; A::NONCE_SIZE];
+        let mut other_nonce = nonce.clone();
+        other_nonce[0] ^= 1;
+        let aad = b"associated data";
+        for len in [0, 1, 15, 16, 17, 63, 64, 65, 200] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let ct = aead.seal(&nonce, aad, &plaintext);
+            assert_eq!(ct.len(), len + A::TAG_SIZE);
+            assert_eq!(aead.open(&nonce, aad, &ct).unwrap(), plaintext);
+
+            for i in [0, ct.len() / 2, ct.len() - 1] {
+                let mut tampered = ct.clone();
+                tampered[i] ^= 0x80;
+                let err = aead.open(&nonce, aad, &tampered).unwrap_err();
+                assert_eq!(err.kind(), ErrorKind::AuthenticationFailed);
+            }
+            let err = aead.open(&nonce, b"other data", &ct).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::AuthenticationFailed);
+            let err = aead.open(&other_nonce, aad, &ct).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::AuthenticationFailed);
+        }
+        let err = aead
+            .open(&nonce, aad, &vec![0u8; A::TAG_SIZE - 1])
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AuthenticationFailed);
+    }
+
+    // A toy encrypt-then-MAC construction, only to exercise the trait itself
+    struct XorThenMac([u8; 16]);
+
+    impl XorThenMac {
+        fn tag(&self, nonce: &[u8], aad: &[u8], ct: &[u8]) -> [u8; 16] {
+            let mut msg = nonce.to_vec();
+            msg.extend_from_slice(aad);
+            msg.extend_from_slice(ct);
+            let mut hmac = Hmac::new(Sha256::new(), &self.0);
+            let mut tag = [0u8; 16];
+            hmac.init();
+            let mut chunks = msg.chunks(64);
+            let last = chunks.next_back().unwrap();
+            for c in chunks {
+                hmac.update(c);
+            }
+            hmac.do_final_truncated(last, &mut tag);
+            tag
+        }
+    }
+
+    impl Aead for XorThenMac {
+        const KEY_SIZE: usize = 16;
+        const NONCE_SIZE: usize = 8;
+        const TAG_SIZE: usize = 16;
+
+        fn seal(&mut self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+            assert_eq!(nonce.len(), Self::NONCE_SIZE);
+            let mut ct: Vec<u8> = plaintext
+                .iter()
+                .zip(self.0.iter().cycle())
+                .map(|(a, b)| a ^ b)
+                .collect();
+            let tag = self.tag(nonce, aad, &ct);
+            ct.extend_from_slice(&tag);
+            ct
+        }
+
+        fn open(&mut self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+            assert_eq!(nonce.len(), Self::NONCE_SIZE);
+            if ciphertext.len() < Self::TAG_SIZE {
+                return Err(ErrorKind::AuthenticationFailed.into());
+            }
+            let (ct, tag) = ciphertext.split_at(ciphertext.len() - Self::TAG_SIZE);
+            if !crate::cmp::eq(&self.tag(nonce, aad, ct), tag) {
+                return Err(ErrorKind::AuthenticationFailed.into());
+            }
+            Ok(ct
+                .iter()
+                .zip(self.0.iter().cycle())
+                .map(|(a, b)| a ^ b)
+                .collect())
+        }
+    }
+
+    #[test]
+    fn aead_round_trip() {
+        round_trip(XorThenMac([0x5a; 16]));
+    }
+
+    #[test]
+    fn aead_round_trip_mut_ref() {
+        let mut aead = XorThenMac([0xa5; 16]);
+        round_trip(&mut aead);
+    }
+
+    #[test]
+    fn aead_round_trip_boxed() {
+        round_trip(Box::new(XorThenMac([0x3c; 16])));
+    }
+
+    #[test]
+    fn nonce_sequence_monotonic() {
+        let mut seq = NonceSequence::<4>::starting_at([0, 0, 0xff, 0xfe]);
+        let mut nonce = [0u8; 4];
+        for expected in [[0, 0, 0xff, 0xfe], [0, 0, 0xff, 0xff], [0, 1, 0, 0]] {
+            seq.next_nonce(&mut nonce).unwrap();
+            assert_eq!(nonce, expected);
+        }
+        assert_eq!(
+            seq.next_nonce(&mut [0u8; 8]).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn nonce_sequence_exhaustion() {
+        let mut seq = NonceSequence::<1>::new();
+        let mut nonce = [0u8; 1];
+        for i in 0..=255u8 {
+            seq.next_nonce(&mut nonce).unwrap();
+            assert_eq!(nonce[0], i);
+        }
+        for _ in 0..2 {
+            assert_eq!(
+                seq.next_nonce(&mut nonce).unwrap_err().kind(),
+                ErrorKind::Other
+            );
+        }
+    }
+
+    #[test]
+    fn aead_seal_next() {
+        let mut aead = XorThenMac([0x11; 16]);
+        let mut seq = NonceSequence::<8>::starting_at([0xff; 8]);
+        let (nonce, ct) = aead.seal_next(&mut seq, b"aad", b"message").unwrap();
+        assert_eq!(nonce, [0xff; 8]);
+        assert_eq!(aead.open(&nonce, b"aad", &ct).unwrap(), b"message");
+        assert!(aead.seal_next(&mut seq, b"aad", b"message").is_err());
+    }
+
+    #[test]
+    fn random_nonce() {
+        let mut rand = DoubleDigestRandom::new(Sha256::new(), Sha256::new());
+        rand.seed([1, 2, 3, 4]);
+        let mut nonces = RandomNonce::new(rand);
+        let mut a = [0u8; 24];
+        let mut b = [0u8; 24];
+        nonces.next_nonce(&mut a).unwrap();
+        nonces.next_nonce(&mut b).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(
+            nonces.next_nonce(&mut [0u8; 12]).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+}
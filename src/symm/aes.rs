@@ -1,7 +1,11 @@
-use alloc::borrow::Cow;
+use alloc::{borrow::Cow, vec::Vec};
 use zeroize::{Zeroize, Zeroizing};
 
 use super::{Operation, SymmetricCipher};
+use crate::error::{Error, ErrorKind, Result};
+
+mod masked;
+pub use masked::MaskedAes;
 
 pub struct Aes<const N: usize> {
     rkeys: [[u8; 16]; 15],
@@ -328,9 +332,8 @@ fn aes_do_dec_first_round(block: &mut [u8], rkey: &[u8; 16]) {
 }
 
 fn aes_encrypt(block: &mut [u8], rkeys: &[[u8; 16]]) {
-    for i in 0..4 {
-        bytemuck::cast_slice_mut::<u8, u32>(block)[i] ^=
-            bytemuck::cast_slice::<u8, u32>(&rkeys[0])[i];
+    for (a, b) in block.iter_mut().zip(&rkeys[0]) {
+        *a ^= b;
     }
     for i in 1..(rkeys.len() - 1) {
         aes_do_enc_round(block, &rkeys[i]);
@@ -343,9 +346,8 @@ fn aes_decrypt(block: &mut [u8], rkeys: &[[u8; 16]]) {
     for i in (1..(rkeys.len() - 1)).rev() {
         aes_do_dec_round(block, &rkeys[i]);
     }
-    for i in 0..4 {
-        bytemuck::cast_slice_mut::<u8, u32>(block)[i] ^=
-            bytemuck::cast_slice::<u8, u32>(&rkeys[0])[i];
+    for (a, b) in block.iter_mut().zip(&rkeys[0]) {
+        *a ^= b;
     }
 }
 
@@ -433,6 +435,96 @@ impl SymmetricCipher for Aes<256> {
     }
 }
 
+const KEY_WRAP_IV: [u8; 8] = [0xa6; 8];
+
+fn key_wrap_with<C: SymmetricCipher>(mut aes: C, kek: &[u8], key_data: &[u8]) -> Vec<u8> {
+    aes.init(kek, Operation::Encrypt);
+    let n = key_data.len() / 8;
+    let mut out = Vec::with_capacity(key_data.len() + 8);
+    out.extend_from_slice(&KEY_WRAP_IV);
+    out.extend_from_slice(key_data);
+    let mut b = Zeroizing::new([0u8; 16]);
+    let mut input = Zeroizing::new([0u8; 16]);
+    for j in 0..6 {
+        for i in 1..=n {
+            input[..8].copy_from_slice(&out[..8]);
+            input[8..].copy_from_slice(&out[8 * i..][..8]);
+            aes.update(&*input, &mut *b);
+            let t = ((n * j + i) as u64).to_be_bytes();
+            for (a, (x, y)) in out[..8].iter_mut().zip(b[..8].iter().zip(&t)) {
+                *a = x ^ y;
+            }
+            out[8 * i..][..8].copy_from_slice(&b[8..]);
+        }
+    }
+    out
+}
+
+fn key_unwrap_with<C: SymmetricCipher>(mut aes: C, kek: &[u8], wrapped: &[u8]) -> Result<Vec<u8>> {
+    aes.init(kek, Operation::Decrypt);
+    let n = wrapped.len() / 8 - 1;
+    let mut a = [0u8; 8];
+    a.copy_from_slice(&wrapped[..8]);
+    let mut r = Zeroizing::new(wrapped[8..].to_vec());
+    let mut b = Zeroizing::new([0u8; 16]);
+    let mut input = Zeroizing::new([0u8; 16]);
+    for j in (0..6).rev() {
+        for i in (1..=n).rev() {
+            let t = ((n * j + i) as u64).to_be_bytes();
+            for (x, (y, z)) in input[..8].iter_mut().zip(a.iter().zip(&t)) {
+                *x = y ^ z;
+            }
+            input[8..].copy_from_slice(&r[8 * (i - 1)..][..8]);
+            aes.update(&*input, &mut *b);
+            a.copy_from_slice(&b[..8]);
+            r[8 * (i - 1)..][..8].copy_from_slice(&b[8..]);
+        }
+    }
+    if !crate::cmp::eq(&a, &KEY_WRAP_IV) {
+        return Err(ErrorKind::AuthenticationFailed.into());
+    }
+    Ok(core::mem::take(&mut *r))
+}
+
+fn invalid_length() -> Error {
+    Error::new_with_message(ErrorKind::InvalidInput, "invalid key wrap length")
+}
+
+///
+/// Wraps `key_data` under the key-encryption key `kek` with the AES key wrap algorithm of RFC 3394.
+///
+/// `kek` may be 16, 24, or 32 bytes, selecting AES-128, AES-192, or AES-256. `key_data` must be a multiple of 8 bytes, and at least 16 bytes.
+/// The result is 8 bytes longer than `key_data`, and includes an integrity check which [`key_unwrap`] verifies.
+/// Returns an error of kind [`ErrorKind::InvalidInput`] if either length is invalid.
+pub fn key_wrap(kek: &[u8], key_data: &[u8]) -> Result<Vec<u8>> {
+    if key_data.len() < 16 || key_data.len() % 8 != 0 {
+        return Err(invalid_length());
+    }
+    match kek.len() {
+        16 => Ok(key_wrap_with(Aes::<128>::const_new(), kek, key_data)),
+        24 => Ok(key_wrap_with(Aes::<192>::const_new(), kek, key_data)),
+        32 => Ok(key_wrap_with(Aes::<256>::const_new(), kek, key_data)),
+        _ => Err(invalid_length()),
+    }
+}
+
+///
+/// Unwraps the output of [`key_wrap`] under the same `kek`.
+///
+/// Returns an error of kind [`ErrorKind::AuthenticationFailed`] if the integrity check fails (the check is compared in constant time, and the tentative key data is zeroed),
+///  or [`ErrorKind::InvalidInput`] if either length is invalid.
+pub fn key_unwrap(kek: &[u8], wrapped: &[u8]) -> Result<Vec<u8>> {
+    if wrapped.len() < 24 || wrapped.len() % 8 != 0 {
+        return Err(invalid_length());
+    }
+    match kek.len() {
+        16 => key_unwrap_with(Aes::<128>::const_new(), kek, wrapped),
+        24 => key_unwrap_with(Aes::<192>::const_new(), kek, wrapped),
+        32 => key_unwrap_with(Aes::<256>::const_new(), kek, wrapped),
+        _ => Err(invalid_length()),
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -507,4 +599,133 @@ mod test {
             ]
         }
     }
+
+    mod key_wrap {
+        use crate::{
+            error::ErrorKind,
+            symm::aes::{key_unwrap, key_wrap},
+        };
+
+        const KEK: [u8; 32] = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            0x1c, 0x1d, 0x1e, 0x1f,
+        ];
+        const KEY_DATA: [u8; 32] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,
+            0x0c, 0x0d, 0x0e, 0x0f,
+        ];
+
+        fn check(kek_len: usize, data_len: usize, expected: &[u8]) {
+            let wrapped = key_wrap(&KEK[..kek_len], &KEY_DATA[..data_len]).unwrap();
+            assert_eq!(wrapped, expected);
+            assert_eq!(
+                key_unwrap(&KEK[..kek_len], &wrapped).unwrap(),
+                &KEY_DATA[..data_len]
+            );
+            let mut tampered = wrapped;
+            tampered[data_len] ^= 1;
+            assert_eq!(
+                key_unwrap(&KEK[..kek_len], &tampered).unwrap_err().kind(),
+                ErrorKind::AuthenticationFailed
+            );
+        }
+
+        #[test]
+        pub fn key_wrap_rfc3394_128_with_128_kek() {
+            // RFC 3394 section 4.1
+            check(
+                16,
+                16,
+                &[
+                    0x1f, 0xa6, 0x8b, 0x0a, 0x81, 0x12, 0xb4, 0x47, 0xae, 0xf3, 0x4b, 0xd8, 0xfb,
+                    0x5a, 0x7b, 0x82, 0x9d, 0x3e, 0x86, 0x23, 0x71, 0xd2, 0xcf, 0xe5,
+                ],
+            );
+        }
+
+        #[test]
+        pub fn key_wrap_rfc3394_128_with_192_kek() {
+            // RFC 3394 section 4.2
+            check(
+                24,
+                16,
+                &[
+                    0x96, 0x77, 0x8b, 0x25, 0xae, 0x6c, 0xa4, 0x35, 0xf9, 0x2b, 0x5b, 0x97, 0xc0,
+                    0x50, 0xae, 0xd2, 0x46, 0x8a, 0xb8, 0xa1, 0x7a, 0xd8, 0x4e, 0x5d,
+                ],
+            );
+        }
+
+        #[test]
+        pub fn key_wrap_rfc3394_128_with_256_kek() {
+            // RFC 3394 section 4.3
+            check(
+                32,
+                16,
+                &[
+                    0x64, 0xe8, 0xc3, 0xf9, 0xce, 0x0f, 0x5b, 0xa2, 0x63, 0xe9, 0x77, 0x79, 0x05,
+                    0x81, 0x8a, 0x2a, 0x93, 0xc8, 0x19, 0x1e, 0x7d, 0x6e, 0x8a, 0xe7,
+                ],
+            );
+        }
+
+        #[test]
+        pub fn key_wrap_rfc3394_192_with_192_kek() {
+            // RFC 3394 section 4.4
+            check(
+                24,
+                24,
+                &[
+                    0x03, 0x1d, 0x33, 0x26, 0x4e, 0x15, 0xd3, 0x32, 0x68, 0xf2, 0x4e, 0xc2, 0x60,
+                    0x74, 0x3e, 0xdc, 0xe1, 0xc6, 0xc7, 0xdd, 0xee, 0x72, 0x5a, 0x93, 0x6b, 0xa8,
+                    0x14, 0x91, 0x5c, 0x67, 0x62, 0xd2,
+                ],
+            );
+        }
+
+        #[test]
+        pub fn key_wrap_rfc3394_192_with_256_kek() {
+            // RFC 3394 section 4.5
+            check(
+                32,
+                24,
+                &[
+                    0xa8, 0xf9, 0xbc, 0x16, 0x12, 0xc6, 0x8b, 0x3f, 0xf6, 0xe6, 0xf4, 0xfb, 0xe3,
+                    0x0e, 0x71, 0xe4, 0x76, 0x9c, 0x8b, 0x80, 0xa3, 0x2c, 0xb8, 0x95, 0x8c, 0xd5,
+                    0xd1, 0x7d, 0x6b, 0x25, 0x4d, 0xa1,
+                ],
+            );
+        }
+
+        #[test]
+        pub fn key_wrap_rfc3394_256_with_256_kek() {
+            // RFC 3394 section 4.6
+            check(
+                32,
+                32,
+                &[
+                    0x28, 0xc9, 0xf4, 0x04, 0xc4, 0xb8, 0x10, 0xf4, 0xcb, 0xcc, 0xb3, 0x5c, 0xfb,
+                    0x87, 0xf8, 0x26, 0x3f, 0x57, 0x86, 0xe2, 0xd8, 0x0e, 0xd3, 0x26, 0xcb, 0xc7,
+                    0xf0, 0xe7, 0x1a, 0x99, 0xf4, 0x3b, 0xfb, 0x98, 0x8b, 0x9b, 0x7a, 0x02, 0xdd,
+                    0x21,
+                ],
+            );
+        }
+
+        #[test]
+        pub fn key_wrap_invalid_lengths() {
+            for (kek, data) in [(16, 8), (16, 20), (20, 16), (0, 16)] {
+                assert_eq!(
+                    key_wrap(&KEK[..kek], &KEY_DATA[..data]).unwrap_err().kind(),
+                    ErrorKind::InvalidInput
+                );
+            }
+            assert_eq!(
+                key_unwrap(&KEK[..16], &KEY_DATA[..16]).unwrap_err().kind(),
+                ErrorKind::InvalidInput
+            );
+        }
+    }
 }
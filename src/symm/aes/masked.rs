@@ -0,0 +1,360 @@
+use alloc::borrow::Cow;
+use core::hint::black_box;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{
+    aes_generate_keys, aes_inv_mix_columns, aes_mix_columns, Aes, INV_SBOX, PERMUTE, SBOX,
+};
+use crate::{
+    rand::SecureRandom,
+    symm::{Operation, SymmetricCipher},
+};
+
+///
+/// The AES state as two shares, whose XOR is the actual state
+struct Shares {
+    data: Zeroizing<[u8; 16]>,
+    mask: Zeroizing<[u8; 16]>,
+}
+
+#[cfg(test)]
+impl Shares {
+    fn value(&self) -> [u8; 16] {
+        let mut v = *self.data;
+        for (a, b) in v.iter_mut().zip(self.mask.iter()) {
+            *a ^= b;
+        }
+        v
+    }
+}
+
+///
+/// Per-block randomness: the S-box input and output masks, and a table which maps `x^m_in` to `S(x)^m_out`
+struct MaskedSbox {
+    m_in: u8,
+    table: Zeroizing<[u8; 256]>,
+    m_out: u8,
+}
+
+impl MaskedSbox {
+    fn new<R: SecureRandom + ?Sized>(sbox: &[u8; 256], rand: &mut R) -> Self {
+        let mut m = [0u8; 2];
+        rand.next_bytes(&mut m);
+        let mut table = Zeroizing::new([0u8; 256]);
+        for (x, &y) in sbox.iter().enumerate() {
+            table[x ^ usize::from(m[0])] = y ^ m[1];
+        }
+        Self {
+            m_in: m[0],
+            table,
+            m_out: m[1],
+        }
+    }
+
+    fn apply(&self, shares: &mut Shares) {
+        for (d, m) in shares.data.iter_mut().zip(shares.mask.iter_mut()) {
+            // Change the mask to m_in without ever combining the data with its own mask
+            *d ^= black_box(*m ^ self.m_in);
+            *d = self.table[usize::from(*d)];
+            *m = self.m_out;
+        }
+    }
+}
+
+///
+/// Replaces the (uniform) mask after the S-box with fresh random bytes, so that MixColumns never combines two bytes under the same mask
+fn refresh<R: SecureRandom + ?Sized>(shares: &mut Shares, rand: &mut R) {
+    let mut r = Zeroizing::new([0u8; 16]);
+    rand.next_bytes(&mut *r);
+    for ((d, m), r) in shares
+        .data
+        .iter_mut()
+        .zip(shares.mask.iter_mut())
+        .zip(r.iter())
+    {
+        *d ^= r;
+        *m ^= r;
+    }
+}
+
+fn shift_rows(block: &mut [u8; 16]) {
+    let mut copied = Zeroizing::new([0u8; 16]);
+    for i in 0..16 {
+        copied[i] = block[PERMUTE[i]];
+    }
+    *block = *copied;
+}
+
+fn inv_shift_rows(block: &mut [u8; 16]) {
+    let mut copied = Zeroizing::new([0u8; 16]);
+    for i in 0..16 {
+        copied[PERMUTE[i]] = block[i];
+    }
+    *block = *copied;
+}
+
+fn add_round_key(shares: &mut Shares, rkey: &[u8; 16]) {
+    for (a, b) in shares.data.iter_mut().zip(rkey) {
+        *a ^= b;
+    }
+}
+
+fn encrypt_shares<R: SecureRandom + ?Sized>(
+    shares: &mut Shares,
+    rkeys: &[[u8; 16]],
+    rand: &mut R,
+    mut after_round: impl FnMut(&Shares),
+) {
+    let sbox = MaskedSbox::new(&SBOX, rand);
+    add_round_key(shares, &rkeys[0]);
+    for (i, rkey) in rkeys.iter().enumerate().skip(1) {
+        sbox.apply(shares);
+        refresh(shares, rand);
+        shift_rows(&mut shares.data);
+        shift_rows(&mut shares.mask);
+        if i != rkeys.len() - 1 {
+            // MixColumns is linear, so it applies to each share separately
+            aes_mix_columns(bytemuck::cast_slice_mut(&mut *shares.data));
+            aes_mix_columns(bytemuck::cast_slice_mut(&mut *shares.mask));
+        }
+        add_round_key(shares, rkey);
+        after_round(shares);
+    }
+}
+
+fn decrypt_shares<R: SecureRandom + ?Sized>(
+    shares: &mut Shares,
+    rkeys: &[[u8; 16]],
+    rand: &mut R,
+    mut after_round: impl FnMut(&Shares),
+) {
+    let sbox = MaskedSbox::new(&INV_SBOX, rand);
+    for (i, rkey) in rkeys.iter().enumerate().skip(1).rev() {
+        add_round_key(shares, rkey);
+        if i != rkeys.len() - 1 {
+            aes_inv_mix_columns(&mut *shares.data);
+            aes_inv_mix_columns(&mut *shares.mask);
+        }
+        inv_shift_rows(&mut shares.data);
+        inv_shift_rows(&mut shares.mask);
+        sbox.apply(shares);
+        refresh(shares, rand);
+        after_round(shares);
+    }
+    add_round_key(shares, &rkeys[0]);
+}
+
+///
+/// AES with first-order boolean masking, as a countermeasure against differential power analysis.
+///
+/// Each block is split into two random shares with `state = data ^ mask`, and the rounds are computed on the shares so that no intermediate value depends on the unmasked state alone.
+/// The S-box is a table recomputed for every block under fresh random input and output masks, and the masks are refreshed after every S-box layer.
+/// Fresh masks are drawn from `R` for every block, so a fast generator should be used.
+///
+/// The key schedule is computed and stored unmasked, and table lookups are indexed by masked values, so this does not protect against cache-timing attacks beyond what [`Aes`] does.
+/// The output is identical to [`Aes`].
+pub struct MaskedAes<const N: usize, R> {
+    rkeys: [[u8; 16]; 15],
+    operation: Option<Operation>,
+    rand: R,
+}
+
+impl<const N: usize, R: SecureRandom> MaskedAes<N, R>
+where
+    Aes<N>: SymmetricCipher,
+{
+    pub fn new(rand: R) -> Self {
+        Self {
+            rkeys: [[0; 16]; 15],
+            operation: None,
+            rand,
+        }
+    }
+
+    fn rounds() -> usize {
+        <Aes<N> as SymmetricCipher>::KEY_SIZE / 4 + 7
+    }
+}
+
+impl<const N: usize, R> Zeroize for MaskedAes<N, R> {
+    fn zeroize(&mut self) {
+        self.rkeys.zeroize()
+    }
+}
+
+impl<const N: usize, R> Drop for MaskedAes<N, R> {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl<const N: usize, R: SecureRandom> SymmetricCipher for MaskedAes<N, R>
+where
+    Aes<N>: SymmetricCipher,
+{
+    const BLOCK_SIZE: usize = 16;
+
+    const KEY_SIZE: usize = <Aes<N> as SymmetricCipher>::KEY_SIZE;
+
+    fn init(&mut self, key: &[u8], op: Operation) {
+        self.zeroize();
+        self.operation = Some(op);
+        aes_generate_keys(key, &mut self.rkeys[..Self::rounds()]);
+    }
+
+    fn update(&mut self, block: &[u8], out: &mut [u8]) {
+        let mut shares = Shares {
+            data: Zeroizing::new([0; 16]),
+            mask: Zeroizing::new([0; 16]),
+        };
+        self.rand.next_bytes(&mut *shares.mask);
+        for ((d, &p), m) in shares.data.iter_mut().zip(block).zip(shares.mask.iter()) {
+            *d = p ^ m;
+        }
+        let rkeys = &self.rkeys[..Self::rounds()];
+        match self.operation {
+            Some(Operation::Encrypt) => encrypt_shares(&mut shares, rkeys, &mut self.rand, |_| {}),
+            Some(Operation::Decrypt) => decrypt_shares(&mut shares, rkeys, &mut self.rand, |_| {}),
+            _ => panic!("Uninitialized"),
+        }
+        for ((o, d), m) in out
+            .iter_mut()
+            .zip(shares.data.iter())
+            .zip(shares.mask.iter())
+        {
+            *o = d ^ m;
+        }
+    }
+
+    fn do_final<'a>(&mut self, block: &[u8], out: &'a mut [u8]) -> Cow<'a, [u8]> {
+        self.update(block, out);
+        Cow::Borrowed(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec::Vec;
+    use zeroize::Zeroizing;
+
+    use super::{decrypt_shares, encrypt_shares, MaskedAes, Shares};
+    use crate::{
+        digest::sha2::Sha256,
+        rand::{DoubleDigestRandom, SecureRandom},
+        symm::{
+            aes::{
+                aes_do_dec_first_round, aes_do_dec_round, aes_do_enc_final_round, aes_do_enc_round,
+                aes_generate_keys, Aes,
+            },
+            Operation, SymmetricCipher,
+        },
+    };
+
+    fn rand() -> DoubleDigestRandom<Sha256, Sha256> {
+        let mut rand = DoubleDigestRandom::new(Sha256::new(), Sha256::new());
+        rand.seed([0x6d61736b6564, 7]);
+        rand
+    }
+
+    fn shares(value: &[u8; 16], rand: &mut impl SecureRandom) -> Shares {
+        let mut mask = Zeroizing::new([0u8; 16]);
+        rand.next_bytes(&mut *mask);
+        let mut data = Zeroizing::new(*value);
+        for (d, m) in data.iter_mut().zip(mask.iter()) {
+            *d ^= m;
+        }
+        Shares { data, mask }
+    }
+
+    #[test]
+    pub fn masked_aes_fips197() {
+        // FIPS 197 appendix C.1
+        let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let plaintext: [u8; 16] = core::array::from_fn(|i| (i * 0x11) as u8);
+        let ciphertext = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ];
+        let mut aes = MaskedAes::<128, _>::new(rand());
+        let mut out = [0u8; 16];
+        aes.init(&key, Operation::Encrypt);
+        aes.update(&plaintext, &mut out);
+        assert_eq!(out, ciphertext);
+        aes.init(&key, Operation::Decrypt);
+        aes.update(&ciphertext, &mut out);
+        assert_eq!(out, plaintext);
+    }
+
+    fn check_matches_aes<const N: usize>(r: &mut impl SecureRandom)
+    where
+        Aes<N>: SymmetricCipher,
+    {
+        let mut key = [0u8; 32];
+        r.next_bytes(&mut key);
+        let key = &key[..N / 8];
+        for _ in 0..8 {
+            let mut block = [0u8; 16];
+            r.next_bytes(&mut block);
+            let (mut expected, mut got) = ([0u8; 16], [0u8; 16]);
+            let mut aes = Aes::<N>::const_new();
+            aes.init(key, Operation::Encrypt);
+            aes.update(&block, &mut expected);
+            let mut masked = MaskedAes::<N, _>::new(rand());
+            masked.init(key, Operation::Encrypt);
+            masked.update(&block, &mut got);
+            assert_eq!(got, expected, "AES-{}", N);
+        }
+    }
+
+    #[test]
+    pub fn masked_aes_matches_aes() {
+        let mut r = rand();
+        check_matches_aes::<128>(&mut r);
+        check_matches_aes::<192>(&mut r);
+        check_matches_aes::<256>(&mut r);
+    }
+
+    #[test]
+    pub fn masked_aes_round_states() {
+        // The XOR of the shares after each round must be the reference AES state after that round
+        let mut r = rand();
+        let mut rkeys = [[0u8; 16]; 15];
+        let key: [u8; 32] = core::array::from_fn(|i| (i * 37 + 5) as u8);
+        aes_generate_keys(&key, &mut rkeys);
+        let block: [u8; 16] = core::array::from_fn(|i| (i * 91 + 3) as u8);
+
+        let mut reference = Vec::new();
+        let mut state = block;
+        for (a, b) in state.iter_mut().zip(&rkeys[0]) {
+            *a ^= b;
+        }
+        for rkey in &rkeys[1..14] {
+            aes_do_enc_round(&mut state, rkey);
+            reference.push(state);
+        }
+        aes_do_enc_final_round(&mut state, &rkeys[14]);
+        reference.push(state);
+        let ciphertext = state;
+
+        let mut masked = Vec::new();
+        let mut s = shares(&block, &mut r);
+        encrypt_shares(&mut s, &rkeys, &mut r, |s| masked.push(s.value()));
+        assert_eq!(masked, reference);
+        assert_eq!(s.value(), ciphertext);
+
+        let mut reference = Vec::new();
+        let mut state = ciphertext;
+        aes_do_dec_first_round(&mut state, &rkeys[14]);
+        reference.push(state);
+        for rkey in rkeys[1..14].iter().rev() {
+            aes_do_dec_round(&mut state, rkey);
+            reference.push(state);
+        }
+
+        let mut masked = Vec::new();
+        let mut s = shares(&ciphertext, &mut r);
+        decrypt_shares(&mut s, &rkeys, &mut r, |s| masked.push(s.value()));
+        assert_eq!(masked, reference);
+        assert_eq!(s.value(), block);
+    }
+}
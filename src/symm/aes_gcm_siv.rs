@@ -0,0 +1,301 @@
+use alloc::{vec, vec::Vec};
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{aead::Aead, aes::Aes, Operation, SymmetricCipher};
+use crate::{
+    digest::Digest,
+    error::{ErrorKind, Result},
+    mac::polyval::Polyval,
+};
+
+// RFC 8452 limits both the plaintext and the associated data to 2^36 bytes
+const MAX_INPUT: u64 = 1 << 36;
+
+///
+/// The AES-GCM-SIV nonce-misuse-resistant AEAD of RFC 8452, keyed with a single AES-`N` key (`N` is 128 or 256).
+///
+/// A fresh authentication key and encryption key are derived from the key-generating key for every nonce, the tag is POLYVAL over the associated data and plaintext,
+///  and the tag is then used as the initial counter for CTR mode. Reusing a nonce only reveals whether two messages (with the same associated data) were identical.
+///
+/// The output is the ciphertext followed by the 16-byte tag, as in RFC 8452.
+pub struct AesGcmSiv<const N: usize>
+where
+    Aes<N>: SymmetricCipher,
+{
+    key_gen: Aes<N>,
+}
+
+impl<const N: usize> AesGcmSiv<N>
+where
+    Aes<N>: SymmetricCipher,
+{
+    ///
+    /// Creates AES-GCM-SIV under the key-generating key `key`.
+    ///
+    /// Panics if `key` is not `N/8` bytes, or if `N` is not 128 or 256.
+    pub fn new(key: &[u8]) -> Self {
+        let key_size = <Aes<N> as SymmetricCipher>::KEY_SIZE;
+        assert!(
+            key_size == 16 || key_size == 32,
+            "AES-GCM-SIV requires AES-128 or AES-256"
+        );
+        assert_eq!(key.len(), key_size, "wrong AES-GCM-SIV key length");
+        let mut key_gen = Aes::<N>::const_new();
+        key_gen.init(key, Operation::Encrypt);
+        Self { key_gen }
+    }
+
+    ///
+    /// Derives the per-nonce authentication key and message encryption key, as in RFC 8452 section 4
+    fn derive_keys(&mut self, nonce: &[u8]) -> (Zeroizing<[u8; 16]>, Aes<N>) {
+        let key_size = <Aes<N> as SymmetricCipher>::KEY_SIZE;
+        let mut material = Zeroizing::new([0u8; 48]);
+        let mut block = [0u8; 16];
+        let mut out = Zeroizing::new([0u8; 16]);
+        block[4..].copy_from_slice(nonce);
+        for i in 0..(16 + key_size) / 8 {
+            block[..4].copy_from_slice(&(i as u32).to_le_bytes());
+            self.key_gen.update(&block, &mut *out);
+            material[8 * i..8 * i + 8].copy_from_slice(&out[..8]);
+        }
+        let mut auth_key = Zeroizing::new([0u8; 16]);
+        auth_key.copy_from_slice(&material[..16]);
+        let mut enc = Aes::<N>::const_new();
+        enc.init(&material[16..16 + key_size], Operation::Encrypt);
+        (auth_key, enc)
+    }
+
+    ///
+    /// Computes the tag over `aad` and `plaintext` with the derived keys
+    fn tag(
+        auth_key: &[u8; 16],
+        enc: &mut Aes<N>,
+        nonce: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> [u8; 16] {
+        let mut polyval = Polyval::new(auth_key);
+        let mut s = Zeroizing::new([0u8; 16]);
+        for data in [aad, plaintext] {
+            let mut chunks = data.chunks_exact(16);
+            for block in &mut chunks {
+                polyval.update(block);
+            }
+            let rem = chunks.remainder();
+            if !rem.is_empty() {
+                let mut pad = Zeroizing::new([0u8; 16]);
+                pad[..rem.len()].copy_from_slice(rem);
+                polyval.update(&*pad);
+            }
+        }
+        let mut lengths = [0u8; 16];
+        lengths[..8].copy_from_slice(&(8 * aad.len() as u64).to_le_bytes());
+        lengths[8..].copy_from_slice(&(8 * plaintext.len() as u64).to_le_bytes());
+        polyval.do_final(&lengths, &mut *s);
+        for (a, b) in s.iter_mut().zip(nonce) {
+            *a ^= b;
+        }
+        s[15] &= 0x7f;
+        let mut tag = [0u8; 16];
+        enc.update(&*s, &mut tag);
+        tag
+    }
+
+    ///
+    /// Applies the CTR keystream whose initial counter block is derived from `tag` to `data` in place.
+    ///
+    /// The counter is the first 32 bits of the block, little-endian, and wraps without carrying into the rest of the block.
+    fn ctr_xor(enc: &mut Aes<N>, tag: &[u8; 16], data: &mut [u8]) {
+        let mut q = *tag;
+        q[15] |= 0x80;
+        let mut ks = Zeroizing::new([0u8; 16]);
+        for chunk in data.chunks_mut(16) {
+            enc.update(&q, &mut *ks);
+            for (a, b) in chunk.iter_mut().zip(ks.iter()) {
+                *a ^= b;
+            }
+            let ctr = u32::from_le_bytes([q[0], q[1], q[2], q[3]]).wrapping_add(1);
+            q[..4].copy_from_slice(&ctr.to_le_bytes());
+        }
+    }
+
+    ///
+    /// Decrypts `ciphertext` (at least 16 bytes) into `out`, which is zeroed if verification fails
+    fn open_into(
+        &mut self,
+        nonce: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+        out: &mut [u8],
+    ) -> Result<()> {
+        let (body, t) = ciphertext.split_at(ciphertext.len() - 16);
+        let mut tag = [0u8; 16];
+        tag.copy_from_slice(t);
+        let (auth_key, mut enc) = self.derive_keys(nonce);
+        out.copy_from_slice(body);
+        Self::ctr_xor(&mut enc, &tag, out);
+        if !crate::cmp::eq(&Self::tag(&auth_key, &mut enc, nonce, aad, out), &tag) {
+            out.zeroize();
+            return Err(ErrorKind::AuthenticationFailed.into());
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Zeroize for AesGcmSiv<N>
+where
+    Aes<N>: SymmetricCipher,
+{
+    fn zeroize(&mut self) {
+        self.key_gen.zeroize();
+    }
+}
+
+///
+/// Panics in `seal` if the plaintext or associated data is longer than the 2^36 bytes RFC 8452 allows.
+impl<const N: usize> Aead for AesGcmSiv<N>
+where
+    Aes<N>: SymmetricCipher,
+{
+    const KEY_SIZE: usize = <Aes<N> as SymmetricCipher>::KEY_SIZE;
+    const NONCE_SIZE: usize = 12;
+    const TAG_SIZE: usize = 16;
+
+    fn seal(&mut self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        assert_eq!(nonce.len(), Self::NONCE_SIZE, "nonce must be 12 bytes");
+        assert!(
+            plaintext.len() as u64 <= MAX_INPUT && aad.len() as u64 <= MAX_INPUT,
+            "input too long for AES-GCM-SIV"
+        );
+        let (auth_key, mut enc) = self.derive_keys(nonce);
+        let tag = Self::tag(&auth_key, &mut enc, nonce, aad, plaintext);
+        let mut out = vec![0u8; plaintext.len() + 16];
+        out[..plaintext.len()].copy_from_slice(plaintext);
+        Self::ctr_xor(&mut enc, &tag, &mut out[..plaintext.len()]);
+        out[plaintext.len()..].copy_from_slice(&tag);
+        out
+    }
+
+    fn open(&mut self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        assert_eq!(nonce.len(), Self::NONCE_SIZE, "nonce must be 12 bytes");
+        if ciphertext.len() < 16
+            || (ciphertext.len() - 16) as u64 > MAX_INPUT
+            || aad.len() as u64 > MAX_INPUT
+        {
+            return Err(ErrorKind::AuthenticationFailed.into());
+        }
+        let mut buf = Zeroizing::new(vec![0u8; ciphertext.len() - 16]);
+        self.open_into(nonce, aad, ciphertext, &mut buf)?;
+        Ok(core::mem::take(&mut *buf))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AesGcmSiv;
+    use crate::{error::ErrorKind, symm::aead::Aead};
+
+    fn nonce() -> [u8; 12] {
+        let mut n = [0u8; 12];
+        n[0] = 3;
+        n
+    }
+
+    fn key<const K: usize>() -> [u8; K] {
+        let mut k = [0u8; K];
+        k[0] = 1;
+        k
+    }
+
+    #[test]
+    pub fn aes_gcm_siv_128_rfc8452() {
+        // RFC 8452 appendix C.1
+        let mut siv = AesGcmSiv::<128>::new(&key::<16>());
+        assert_eq!(
+            siv.seal(&nonce(), &[], &[]),
+            [
+                0xdc, 0x20, 0xe2, 0xd8, 0x3f, 0x25, 0x70, 0x5b, 0xb4, 0x9e, 0x43, 0x9e, 0xca, 0x56,
+                0xde, 0x25
+            ]
+        );
+        let pt = [1, 0, 0, 0, 0, 0, 0, 0];
+        let ct = [
+            0xb5, 0xd8, 0x39, 0x33, 0x0a, 0xc7, 0xb7, 0x86, 0x57, 0x87, 0x82, 0xff, 0xf6, 0x01,
+            0x3b, 0x81, 0x5b, 0x28, 0x7c, 0x22, 0x49, 0x3a, 0x36, 0x4c,
+        ];
+        assert_eq!(siv.seal(&nonce(), &[], &pt), ct);
+        assert_eq!(siv.open(&nonce(), &[], &ct).unwrap(), pt);
+
+        let mut pt = [0u8; 48];
+        pt[0] = 1;
+        pt[16] = 2;
+        pt[32] = 3;
+        let ct = [
+            0x3f, 0xd2, 0x4c, 0xe1, 0xf5, 0xa6, 0x7b, 0x75, 0xbf, 0x23, 0x51, 0xf1, 0x81, 0xa4,
+            0x75, 0xc7, 0xb8, 0x00, 0xa5, 0xb4, 0xd3, 0xdc, 0xf7, 0x01, 0x06, 0xb1, 0xee, 0xa8,
+            0x2f, 0xa1, 0xd6, 0x4d, 0xf4, 0x2b, 0xf7, 0x22, 0x61, 0x22, 0xfa, 0x92, 0xe1, 0x7a,
+            0x40, 0xee, 0xaa, 0xc1, 0x20, 0x1b, 0x5e, 0x6e, 0x31, 0x1d, 0xbf, 0x39, 0x5d, 0x35,
+            0xb0, 0xfe, 0x39, 0xc2, 0x71, 0x43, 0x88, 0xf8,
+        ];
+        assert_eq!(siv.seal(&nonce(), &[], &pt), ct);
+        assert_eq!(siv.open(&nonce(), &[], &ct).unwrap(), pt);
+    }
+
+    #[test]
+    pub fn aes_gcm_siv_128_rfc8452_aad() {
+        // RFC 8452 appendix C.1
+        let mut siv = AesGcmSiv::<128>::new(&key::<16>());
+        let pt = [2, 0, 0, 0, 0, 0, 0, 0];
+        let ct = [
+            0x1e, 0x6d, 0xab, 0xa3, 0x56, 0x69, 0xf4, 0x27, 0x3b, 0x0a, 0x1a, 0x25, 0x60, 0x96,
+            0x9c, 0xdf, 0x79, 0x0d, 0x99, 0x75, 0x9a, 0xbd, 0x15, 0x08,
+        ];
+        assert_eq!(siv.seal(&nonce(), &[1], &pt), ct);
+        assert_eq!(siv.open(&nonce(), &[1], &ct).unwrap(), pt);
+        assert_eq!(
+            siv.open(&nonce(), &[2], &ct).unwrap_err().kind(),
+            ErrorKind::AuthenticationFailed
+        );
+    }
+
+    #[test]
+    pub fn aes_gcm_siv_256_rfc8452() {
+        // RFC 8452 appendix C.2
+        let mut siv = AesGcmSiv::<256>::new(&key::<32>());
+        assert_eq!(
+            siv.seal(&nonce(), &[], &[]),
+            [
+                0x07, 0xf5, 0xf4, 0x16, 0x9b, 0xbf, 0x55, 0xa8, 0x40, 0x0c, 0xd4, 0x7e, 0xa6, 0xfd,
+                0x40, 0x0f
+            ]
+        );
+        let pt = [1, 0, 0, 0, 0, 0, 0, 0];
+        let ct = [
+            0xc2, 0xef, 0x32, 0x8e, 0x5c, 0x71, 0xc8, 0x3b, 0x84, 0x31, 0x22, 0x13, 0x0f, 0x73,
+            0x64, 0xb7, 0x61, 0xe0, 0xb9, 0x74, 0x27, 0xe3, 0xdf, 0x28,
+        ];
+        assert_eq!(siv.seal(&nonce(), &[], &pt), ct);
+        assert_eq!(siv.open(&nonce(), &[], &ct).unwrap(), pt);
+    }
+
+    #[test]
+    pub fn aes_gcm_siv_round_trip() {
+        crate::symm::aead::test::round_trip(AesGcmSiv::<128>::new(&[0x33; 16]));
+        crate::symm::aead::test::round_trip(AesGcmSiv::<256>::new(&[0x44; 32]));
+    }
+
+    #[test]
+    pub fn aes_gcm_siv_failed_open_clears_plaintext() {
+        let mut siv = AesGcmSiv::<128>::new(&[0x55; 16]);
+        let mut ct = siv.seal(&[7; 12], b"ad", b"attack at dawn");
+        ct[0] ^= 1;
+        let mut scratch = [0xffu8; 14];
+        assert_eq!(
+            siv.open_into(&[7; 12], b"ad", &ct, &mut scratch)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::AuthenticationFailed
+        );
+        assert_eq!(scratch, [0u8; 14]);
+    }
+}
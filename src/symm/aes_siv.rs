@@ -0,0 +1,267 @@
+use alloc::{vec, vec::Vec};
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{aead::Aead, aes::Aes, Operation, SymmetricCipher};
+use crate::{
+    digest::digest,
+    error::{ErrorKind, Result},
+    mac::cmac::{dbl, Cmac},
+};
+
+///
+/// The AES-SIV deterministic AEAD of RFC 5297, keyed with two AES-`N` keys (so AES-SIV-256 is `AesSiv<128>`).
+///
+/// The synthetic IV is a CMAC (via S2V) over the associated data and the plaintext, and it is used both as the tag and as the CTR mode IV.
+/// Reusing a nonce therefore only reveals whether two messages (with the same associated data) were identical, rather than breaking confidentiality and authenticity.
+///
+/// The output is the 16-byte synthetic IV followed by the ciphertext, as in RFC 5297.
+/// [`AesSiv::seal_vectored`] accepts any number of associated data components, and with no nonce gives the deterministic mode of the RFC.
+pub struct AesSiv<const N: usize>
+where
+    Aes<N>: SymmetricCipher,
+{
+    mac: Cmac<Aes<N>>,
+    ctr: Aes<N>,
+}
+
+impl<const N: usize> AesSiv<N>
+where
+    Aes<N>: SymmetricCipher,
+{
+    ///
+    /// Creates AES-SIV under `key`, which is the CMAC key followed by the CTR key.
+    ///
+    /// Panics if `key` is not `2*N/8` bytes.
+    pub fn new(key: &[u8]) -> Self {
+        let half = <Aes<N> as SymmetricCipher>::KEY_SIZE;
+        assert_eq!(key.len(), 2 * half, "wrong AES-SIV key length");
+        let mut ctr = Aes::<N>::const_new();
+        ctr.init(&key[half..], Operation::Encrypt);
+        Self {
+            mac: Cmac::new(Aes::<N>::const_new(), &key[..half]),
+            ctr,
+        }
+    }
+
+    fn cmac(&mut self, bytes: &[u8]) -> [u8; 16] {
+        let mut out = [0u8; 16];
+        digest(&mut self.mac, bytes, &mut out);
+        out
+    }
+
+    ///
+    /// The S2V function of RFC 5297 section 2.4, over the components of `ad` followed by `plaintext`
+    fn s2v(&mut self, ad: &[&[u8]], plaintext: &[u8]) -> [u8; 16] {
+        let mut d = Zeroizing::new(self.cmac(&[0; 16]));
+        for s in ad {
+            dbl(&mut d);
+            for (a, b) in d.iter_mut().zip(self.cmac(s).iter()) {
+                *a ^= b;
+            }
+        }
+        let mut t = Zeroizing::new(plaintext.to_vec());
+        if t.len() >= 16 {
+            let off = t.len() - 16;
+            for (a, b) in t[off..].iter_mut().zip(d.iter()) {
+                *a ^= b;
+            }
+        } else {
+            dbl(&mut d);
+            for (a, b) in d.iter_mut().zip(t.iter()) {
+                *a ^= b;
+            }
+            d[t.len()] ^= 0x80;
+            t.clear();
+            t.extend_from_slice(&*d);
+        }
+        self.cmac(&t)
+    }
+
+    ///
+    /// Applies the CTR keystream starting at `iv` (with bits 31 and 63 cleared, as RFC 5297 requires) to `data` in place
+    fn ctr_xor(&mut self, iv: &[u8; 16], data: &mut [u8]) {
+        let mut q = *iv;
+        q[8] &= 0x7f;
+        q[12] &= 0x7f;
+        let mut ks = Zeroizing::new([0u8; 16]);
+        for chunk in data.chunks_mut(16) {
+            self.ctr.update(&q, &mut *ks);
+            for (a, b) in chunk.iter_mut().zip(ks.iter()) {
+                *a ^= b;
+            }
+            for b in q.iter_mut().rev() {
+                let (v, overflow) = b.overflowing_add(1);
+                *b = v;
+                if !overflow {
+                    break;
+                }
+            }
+        }
+    }
+
+    ///
+    /// Encrypts `plaintext`, authenticating it together with each component of `ad` (which may include a nonce as its last component).
+    ///
+    /// Returns the synthetic IV followed by the ciphertext.
+    pub fn seal_vectored(&mut self, ad: &[&[u8]], plaintext: &[u8]) -> Vec<u8> {
+        let v = self.s2v(ad, plaintext);
+        let mut out = vec![0u8; 16 + plaintext.len()];
+        out[..16].copy_from_slice(&v);
+        out[16..].copy_from_slice(plaintext);
+        self.ctr_xor(&v, &mut out[16..]);
+        out
+    }
+
+    ///
+    /// Verifies and decrypts the output of [`AesSiv::seal_vectored`] with the same associated data components.
+    ///
+    /// Returns an error of kind [`ErrorKind::AuthenticationFailed`] if the ciphertext or any component was modified.
+    /// The tentative plaintext is held in a buffer which is zeroed on failure, and is only returned once the synthetic IV has been verified.
+    pub fn open_vectored(&mut self, ad: &[&[u8]], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        if ciphertext.len() < 16 {
+            return Err(ErrorKind::AuthenticationFailed.into());
+        }
+        let mut buf = Zeroizing::new(vec![0u8; ciphertext.len() - 16]);
+        self.open_into(ad, ciphertext, &mut buf)?;
+        Ok(core::mem::take(&mut *buf))
+    }
+
+    ///
+    /// Decrypts `ciphertext` (at least 16 bytes) into `out`, which is zeroed if verification fails
+    fn open_into(&mut self, ad: &[&[u8]], ciphertext: &[u8], out: &mut [u8]) -> Result<()> {
+        let mut v = [0u8; 16];
+        v.copy_from_slice(&ciphertext[..16]);
+        out.copy_from_slice(&ciphertext[16..]);
+        self.ctr_xor(&v, out);
+        if !crate::cmp::eq(&self.s2v(ad, out), &v) {
+            out.zeroize();
+            return Err(ErrorKind::AuthenticationFailed.into());
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Zeroize for AesSiv<N>
+where
+    Aes<N>: SymmetricCipher,
+{
+    fn zeroize(&mut self) {
+        self.mac.zeroize();
+        self.ctr.zeroize();
+    }
+}
+
+///
+/// Seals with the associated data and then the nonce as the two S2V components, as in RFC 5297 section 3.
+/// The output has the tag first, as in [`AesSiv::seal_vectored`].
+impl<const N: usize> Aead for AesSiv<N>
+where
+    Aes<N>: SymmetricCipher,
+{
+    const KEY_SIZE: usize = 2 * <Aes<N> as SymmetricCipher>::KEY_SIZE;
+    const NONCE_SIZE: usize = 16;
+    const TAG_SIZE: usize = 16;
+
+    fn seal(&mut self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        assert_eq!(nonce.len(), Self::NONCE_SIZE, "nonce must be 16 bytes");
+        self.seal_vectored(&[aad, nonce], plaintext)
+    }
+
+    fn open(&mut self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        assert_eq!(nonce.len(), Self::NONCE_SIZE, "nonce must be 16 bytes");
+        self.open_vectored(&[aad, nonce], ciphertext)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AesSiv;
+    use crate::error::ErrorKind;
+
+    #[test]
+    pub fn aes_siv_rfc5297_deterministic() {
+        // RFC 5297 appendix A.1
+        let key = [
+            0xff, 0xfe, 0xfd, 0xfc, 0xfb, 0xfa, 0xf9, 0xf8, 0xf7, 0xf6, 0xf5, 0xf4, 0xf3, 0xf2,
+            0xf1, 0xf0, 0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb,
+            0xfc, 0xfd, 0xfe, 0xff,
+        ];
+        let ad: [u8; 24] = core::array::from_fn(|i| 0x10 + i as u8);
+        let plaintext = [
+            0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+        ];
+        let expected = [
+            0x85, 0x63, 0x2d, 0x07, 0xc6, 0xe8, 0xf3, 0x7f, 0x95, 0x0a, 0xcd, 0x32, 0x0a, 0x2e,
+            0xcc, 0x93, 0x40, 0xc0, 0x2b, 0x96, 0x90, 0xc4, 0xdc, 0x04, 0xda, 0xef, 0x7f, 0x6a,
+            0xfe, 0x5c,
+        ];
+        let mut siv = AesSiv::<128>::new(&key);
+        assert_eq!(siv.seal_vectored(&[&ad], &plaintext), expected);
+        assert_eq!(siv.open_vectored(&[&ad], &expected).unwrap(), plaintext);
+        let mut tampered = expected;
+        tampered[20] ^= 1;
+        assert_eq!(
+            siv.open_vectored(&[&ad], &tampered).unwrap_err().kind(),
+            ErrorKind::AuthenticationFailed
+        );
+    }
+
+    #[test]
+    pub fn aes_siv_rfc5297_nonce() {
+        // RFC 5297 appendix A.2
+        let key = [
+            0x7f, 0x7e, 0x7d, 0x7c, 0x7b, 0x7a, 0x79, 0x78, 0x77, 0x76, 0x75, 0x74, 0x73, 0x72,
+            0x71, 0x70, 0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b,
+            0x4c, 0x4d, 0x4e, 0x4f,
+        ];
+        let ad1 = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff, 0xde, 0xad, 0xda, 0xda, 0xde, 0xad, 0xda, 0xda, 0xff, 0xee, 0xdd, 0xcc,
+            0xbb, 0xaa, 0x99, 0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00,
+        ];
+        let ad2 = [0x10, 0x20, 0x30, 0x40, 0x50, 0x60, 0x70, 0x80, 0x90, 0xa0];
+        let nonce = [
+            0x09, 0xf9, 0x11, 0x02, 0x9d, 0x74, 0xe3, 0x5b, 0xd8, 0x41, 0x56, 0xc5, 0x63, 0x56,
+            0x88, 0xc0,
+        ];
+        let plaintext = b"this is some plaintext to encrypt using SIV-AES";
+        let expected = [
+            0x7b, 0xdb, 0x6e, 0x3b, 0x43, 0x26, 0x67, 0xeb, 0x06, 0xf4, 0xd1, 0x4b, 0xff, 0x2f,
+            0xbd, 0x0f, 0xcb, 0x90, 0x0f, 0x2f, 0xdd, 0xbe, 0x40, 0x43, 0x26, 0x60, 0x19, 0x65,
+            0xc8, 0x89, 0xbf, 0x17, 0xdb, 0xa7, 0x7c, 0xeb, 0x09, 0x4f, 0xa6, 0x63, 0xb7, 0xa3,
+            0xf7, 0x48, 0xba, 0x8a, 0xf8, 0x29, 0xea, 0x64, 0xad, 0x54, 0x4a, 0x27, 0x2e, 0x9c,
+            0x48, 0x5b, 0x62, 0xa3, 0xfd, 0x5c, 0x0d,
+        ];
+        let mut siv = AesSiv::<128>::new(&key);
+        assert_eq!(
+            siv.seal_vectored(&[&ad1, &ad2, &nonce], plaintext),
+            expected
+        );
+        assert_eq!(
+            siv.open_vectored(&[&ad1, &ad2, &nonce], &expected).unwrap(),
+            plaintext
+        );
+    }
+
+    #[test]
+    pub fn aes_siv_round_trip() {
+        crate::symm::aead::test::round_trip(AesSiv::<128>::new(&[0x33; 32]));
+        crate::symm::aead::test::round_trip(AesSiv::<256>::new(&[0x44; 64]));
+    }
+
+    #[test]
+    pub fn aes_siv_failed_open_clears_plaintext() {
+        let mut siv = AesSiv::<128>::new(&[0x55; 32]);
+        let mut ct = siv.seal_vectored(&[b"ad"], b"attack at dawn");
+        ct[16] ^= 1;
+        let mut scratch = [0xffu8; 14];
+        assert_eq!(
+            siv.open_into(&[b"ad"], &ct, &mut scratch)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::AuthenticationFailed
+        );
+        assert_eq!(scratch, [0u8; 14]);
+        assert!(siv.open_vectored(&[b"ad"], &ct).is_err());
+    }
+}
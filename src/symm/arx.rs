@@ -0,0 +1,95 @@
+//!
+//! The add-rotate-xor quarter rounds shared by the ChaCha and Salsa20 families.
+//!
+//! Both operate in place on four words of a state array, selected by index, and use only additions, rotations, and xors, so their timing never depends on the state.
+
+///
+/// The ChaCha quarter round of RFC 8439 section 2.1, applied to `s[a]`, `s[b]`, `s[c]`, and `s[d]`
+#[inline]
+pub fn chacha_quarter_round<const N: usize>(
+    s: &mut [u32; N],
+    a: usize,
+    b: usize,
+    c: usize,
+    d: usize,
+) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+///
+/// The Salsa20 quarter round, applied to `s[a]`, `s[b]`, `s[c]`, and `s[d]` (in the order `y0`, `y1`, `y2`, `y3` of the Salsa20 specification)
+#[inline]
+pub fn salsa_quarter_round<const N: usize>(
+    s: &mut [u32; N],
+    a: usize,
+    b: usize,
+    c: usize,
+    d: usize,
+) {
+    s[b] ^= s[a].wrapping_add(s[d]).rotate_left(7);
+    s[c] ^= s[b].wrapping_add(s[a]).rotate_left(9);
+    s[d] ^= s[c].wrapping_add(s[b]).rotate_left(13);
+    s[a] ^= s[d].wrapping_add(s[c]).rotate_left(18);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{chacha_quarter_round, salsa_quarter_round};
+
+    #[test]
+    pub fn chacha_quarter_round_rfc8439() {
+        // RFC 8439 section 2.1.1
+        let mut s = [0x11111111, 0x01020304, 0x9b8d6f43, 0x01234567];
+        chacha_quarter_round(&mut s, 0, 1, 2, 3);
+        assert_eq!(s, [0xea2a92f4, 0xcb1cf8ce, 0x4581472e, 0x5881c4bb]);
+    }
+
+    #[test]
+    pub fn chacha_quarter_round_on_state_rfc8439() {
+        // RFC 8439 section 2.2.1
+        let mut s = [
+            0x879531e0, 0xc5ecf37d, 0x516461b1, 0xc9a62f8a, 0x44c20ef3, 0x3390af7f, 0xd9fc690b,
+            0x2a5f714c, 0x53372767, 0xb00a5631, 0x974c541a, 0x359e9963, 0x5c971061, 0x3d631689,
+            0x2098d9d6, 0x91dbd320,
+        ];
+        chacha_quarter_round(&mut s, 2, 7, 8, 13);
+        assert_eq!(
+            s,
+            [
+                0x879531e0, 0xc5ecf37d, 0xbdb886dc, 0xc9a62f8a, 0x44c20ef3, 0x3390af7f, 0xd9fc690b,
+                0xcfacafd2, 0xe46bea80, 0xb00a5631, 0x974c541a, 0x359e9963, 0x5c971061, 0xccc07c79,
+                0x2098d9d6, 0x91dbd320,
+            ]
+        );
+    }
+
+    #[test]
+    pub fn salsa_quarter_round_spec() {
+        // The quarterround examples of the Salsa20 specification
+        let cases: [([u32; 4], [u32; 4]); 3] = [
+            (
+                [1, 0, 0, 0],
+                [0x08008145, 0x00000080, 0x00010200, 0x20500000],
+            ),
+            (
+                [0, 1, 0, 0],
+                [0x88000100, 0x00000001, 0x00000200, 0x00402000],
+            ),
+            (
+                [0xd3917c5b, 0x55f1c407, 0x52a58a7a, 0x8f887a3b],
+                [0x3e2f308c, 0xd90a8f36, 0x6ab2a923, 0x2883524c],
+            ),
+        ];
+        for (mut s, expected) in cases {
+            salsa_quarter_round(&mut s, 0, 1, 2, 3);
+            assert_eq!(s, expected);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use alloc::borrow::Cow;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{arx::chacha_quarter_round, Operation, SymmetricCipher};
+
+const SIGMA: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+fn chacha20_rounds(s: &mut [u32; 16]) {
+    for _ in 0..10 {
+        chacha_quarter_round(s, 0, 4, 8, 12);
+        chacha_quarter_round(s, 1, 5, 9, 13);
+        chacha_quarter_round(s, 2, 6, 10, 14);
+        chacha_quarter_round(s, 3, 7, 11, 15);
+        chacha_quarter_round(s, 0, 5, 10, 15);
+        chacha_quarter_round(s, 1, 6, 11, 12);
+        chacha_quarter_round(s, 2, 7, 8, 13);
+        chacha_quarter_round(s, 3, 4, 9, 14);
+    }
+}
+
+pub(crate) fn load_words<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    let mut ret = [0u32; N];
+    for (w, b) in ret.iter_mut().zip(bytes.chunks_exact(4)) {
+        *w = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    }
+    ret
+}
+
+///
+/// Computes the ChaCha20 block function of RFC 8439, writing 64 bytes of keystream to `out`
+pub(crate) fn chacha20_block(key: &[u32; 8], counter: u32, nonce: &[u32; 3], out: &mut [u8; 64]) {
+    let mut init = Zeroizing::new([0u32; 16]);
+    init[..4].copy_from_slice(&SIGMA);
+    init[4..12].copy_from_slice(key);
+    init[12] = counter;
+    init[13..].copy_from_slice(nonce);
+    let mut s = Zeroizing::new(*init);
+    chacha20_rounds(&mut s);
+    for (i, o) in out.chunks_exact_mut(4).enumerate() {
+        o.copy_from_slice(&s[i].wrapping_add(init[i]).to_le_bytes());
+    }
+}
+
+///
+/// Computes HChaCha20, which derives a 256-bit subkey from `key` and a 128-bit `nonce`.
+///
+/// This is the first step of the extended-nonce XChaCha20 construction (see [`super::chacha20poly1305::XChaCha20Poly1305`]),
+///  and is also usable on its own as a key derivation step when `nonce` is not secret. It does not depend on [`ChaCha20`], only on the 20 ChaCha rounds.
+///
+/// ## Examples
+///
+/// ```
+/// use lc_crypto::symm::chacha20::hchacha20;
+/// let subkey = hchacha20(&[0x42; 32], &[0; 16]);
+/// assert_ne!(*subkey, [0x42; 32]);
+/// ```
+pub fn hchacha20(key: &[u8; 32], nonce: &[u8; 16]) -> Zeroizing<[u8; 32]> {
+    let mut s = Zeroizing::new([0u32; 16]);
+    s[..4].copy_from_slice(&SIGMA);
+    s[4..12].copy_from_slice(&load_words::<8>(key));
+    s[12..].copy_from_slice(&load_words::<4>(nonce));
+    chacha20_rounds(&mut s);
+    let mut out = Zeroizing::new([0u8; 32]);
+    for (o, w) in out.chunks_exact_mut(4).zip(s[..4].iter().chain(&s[12..])) {
+        o.copy_from_slice(&w.to_le_bytes());
+    }
+    out
+}
+
+///
+/// The ChaCha20 stream cipher of RFC 8439, with a 96-bit nonce and 32-bit block counter.
+///
+/// Encryption and decryption are the same operation. Each full 64-byte block passed to [`SymmetricCipher::update`] advances the counter by one,
+///  and [`SymmetricCipher::do_final`] accepts a partial block, so no padding is needed.
+/// [`SymmetricCipher::init`] rewinds the counter to the value given to [`ChaCha20::new`].
+///
+/// A nonce must never be reused with the same key. For the same reason, the counter never wraps around:
+///  asking for another block after the one at counter `2^32-1` panics.
+pub struct ChaCha20 {
+    key: Zeroizing<[u32; 8]>,
+    nonce: [u32; 3],
+    initial_counter: u32,
+    counter: u32,
+    exhausted: bool,
+}
+
+impl ChaCha20 {
+    pub fn new(nonce: &[u8; 12], counter: u32) -> Self {
+        Self {
+            key: Zeroizing::new([0; 8]),
+            nonce: load_words(nonce),
+            initial_counter: counter,
+            counter,
+            exhausted: false,
+        }
+    }
+
+    ///
+    /// Writes the keystream block at the current counter to `ks`, and advances the counter
+    fn next_block(&mut self, ks: &mut [u8; 64]) {
+        assert!(!self.exhausted, "ChaCha20 block counter exhausted");
+        chacha20_block(&self.key, self.counter, &self.nonce, ks);
+        match self.counter.checked_add(1) {
+            Some(counter) => self.counter = counter,
+            None => self.exhausted = true,
+        }
+    }
+
+    fn apply_keystream(&mut self, block: &[u8], out: &mut [u8]) {
+        let mut ks = Zeroizing::new([0u8; 64]);
+        self.next_block(&mut ks);
+        for ((o, b), k) in out.iter_mut().zip(block).zip(ks.iter()) {
+            *o = b ^ k;
+        }
+    }
+
+    ///
+    /// Encrypts or decrypts `buf` in place, for plaintext which should stay in zeroizing memory.
+    ///
+    /// The keystream is XORed directly into `buf`, so the plaintext is never copied out of it, and the keystream blocks are zeroized after use.
+    /// `buf` may have any length, and continues from the current counter: each 64 bytes advance it by one, and a trailing partial block
+    ///  consumes a whole block, as with [`SymmetricCipher::do_final`]. Like the cipher path, it panics rather than wrap the counter around.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use lc_crypto::symm::{chacha20::ChaCha20, Operation, SymmetricCipher};
+    /// use zeroize::Zeroizing;
+    /// let mut cipher = ChaCha20::new(&[7; 12], 0);
+    /// cipher.init(&[0x42; 32], Operation::Encrypt);
+    /// let mut buf = Zeroizing::new(b"attack at dawn".to_vec());
+    /// cipher.apply_keystream_secret(&mut buf);
+    /// cipher.init(&[0x42; 32], Operation::Decrypt);
+    /// cipher.apply_keystream_secret(&mut buf);
+    /// assert_eq!(buf[..], b"attack at dawn"[..]);
+    /// ```
+    pub fn apply_keystream_secret<B: AsMut<[u8]> + Zeroize>(&mut self, buf: &mut Zeroizing<B>) {
+        let mut ks = Zeroizing::new([0u8; 64]);
+        for block in (**buf).as_mut().chunks_mut(64) {
+            self.next_block(&mut ks);
+            for (b, k) in block.iter_mut().zip(ks.iter()) {
+                *b ^= k;
+            }
+        }
+    }
+}
+
+impl Zeroize for ChaCha20 {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+        self.nonce.zeroize();
+        self.counter = self.initial_counter;
+        self.exhausted = false;
+    }
+}
+
+impl SymmetricCipher for ChaCha20 {
+    const BLOCK_SIZE: usize = 64;
+
+    const KEY_SIZE: usize = 32;
+
+    fn init(&mut self, key: &[u8], _: Operation) {
+        assert_eq!(key.len(), Self::KEY_SIZE);
+        *self.key = load_words(key);
+        self.counter = self.initial_counter;
+        self.exhausted = false;
+    }
+
+    fn update(&mut self, block: &[u8], out: &mut [u8]) {
+        assert_eq!(block.len(), Self::BLOCK_SIZE);
+        self.apply_keystream(block, out);
+    }
+
+    fn do_final<'a>(&mut self, block: &[u8], out: &'a mut [u8]) -> Cow<'a, [u8]> {
+        assert!(block.len() <= Self::BLOCK_SIZE);
+        self.apply_keystream(block, out);
+        Cow::Borrowed(&out[..block.len()])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{chacha20_block, hchacha20, load_words, ChaCha20};
+    use crate::symm::{Operation, SymmetricCipher};
+    use zeroize::Zeroizing;
+
+    #[test]
+    pub fn chacha20_block_rfc8439() {
+        // RFC 8439 section 2.3.2
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let nonce = [0, 0, 0, 9, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let mut out = [0u8; 64];
+        chacha20_block(&load_words(&key), 1, &load_words(&nonce), &mut out);
+        assert_eq!(
+            out,
+            [
+                0x10, 0xf1, 0xe7, 0xe4, 0xd1, 0x3b, 0x59, 0x15, 0x50, 0x0f, 0xdd, 0x1f, 0xa3, 0x20,
+                0x71, 0xc4, 0xc7, 0xd1, 0xf4, 0xc7, 0x33, 0xc0, 0x68, 0x03, 0x04, 0x22, 0xaa, 0x9a,
+                0xc3, 0xd4, 0x6c, 0x4e, 0xd2, 0x82, 0x64, 0x46, 0x07, 0x9f, 0xaa, 0x09, 0x14, 0xc2,
+                0xd7, 0x05, 0xd9, 0x8b, 0x02, 0xa2, 0xb5, 0x12, 0x9c, 0xd1, 0xde, 0x16, 0x4e, 0xb9,
+                0xcb, 0xd0, 0x83, 0xe8, 0xa2, 0x50, 0x3c, 0x4e
+            ]
+        );
+    }
+
+    #[test]
+    pub fn chacha20_encrypt_rfc8439() {
+        // RFC 8439 section 2.4.2
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let nonce = [0, 0, 0, 0, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+        let mut cipher = ChaCha20::new(&nonce, 1);
+        cipher.init(&key, Operation::Encrypt);
+        let mut out = [0u8; 114];
+        let mut chunks = plaintext.chunks(64);
+        let last = chunks.next_back().unwrap();
+        let mut pos = 0;
+        for c in chunks {
+            cipher.update(c, &mut out[pos..pos + 64]);
+            pos += 64;
+        }
+        let tail = cipher.do_final(last, &mut out[pos..]).len();
+        assert_eq!(pos + tail, plaintext.len());
+        assert_eq!(
+            out[..16],
+            [
+                0x6e, 0x2e, 0x35, 0x9a, 0x25, 0x68, 0xf9, 0x80, 0x41, 0xba, 0x07, 0x28, 0xdd, 0x0d,
+                0x69, 0x81
+            ]
+        );
+        assert_eq!(out[112..], [0x87, 0x4d]);
+
+        let mut back = [0u8; 114];
+        cipher.init(&key, Operation::Decrypt);
+        cipher.update(&out[..64], &mut back[..64]);
+        cipher.do_final(&out[64..], &mut back[64..]);
+        assert_eq!(back[..], plaintext[..]);
+    }
+
+    #[test]
+    pub fn chacha20_last_counter_block() {
+        // The block at counter 2^32-1 is still available
+        let mut cipher = ChaCha20::new(&[0; 12], u32::MAX);
+        cipher.init(&[1; 32], Operation::Encrypt);
+        let mut out = [0u8; 64];
+        cipher.update(&[0; 64], &mut out);
+        let mut expected = [0u8; 64];
+        chacha20_block(&[0x01010101; 8], u32::MAX, &[0; 3], &mut expected);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn chacha20_counter_does_not_wrap() {
+        let mut cipher = ChaCha20::new(&[0; 12], u32::MAX);
+        cipher.init(&[1; 32], Operation::Encrypt);
+        let mut out = [0u8; 64];
+        cipher.update(&[0; 64], &mut out);
+        cipher.do_final(&[0; 1], &mut out);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn chacha20_secret_counter_does_not_wrap() {
+        let mut cipher = ChaCha20::new(&[0; 12], u32::MAX);
+        cipher.init(&[1; 32], Operation::Encrypt);
+        cipher.apply_keystream_secret(&mut Zeroizing::new([0u8; 65]));
+    }
+
+    #[test]
+    pub fn chacha20_secret_in_place() {
+        let key = [0x5au8; 32];
+        let nonce = [3u8; 12];
+        for len in [0, 1, 63, 64, 65, 200] {
+            let plaintext: alloc::vec::Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut buf = Zeroizing::new(plaintext.clone());
+            let mut cipher = ChaCha20::new(&nonce, 1);
+            cipher.init(&key, Operation::Encrypt);
+            cipher.apply_keystream_secret(&mut buf);
+
+            // Matches the block-by-block cipher path
+            let mut expected = alloc::vec![0u8; len];
+            cipher.init(&key, Operation::Encrypt);
+            let mut pos = 0;
+            while len - pos > 64 {
+                cipher.update(&plaintext[pos..pos + 64], &mut expected[pos..pos + 64]);
+                pos += 64;
+            }
+            cipher.do_final(&plaintext[pos..], &mut expected[pos..]);
+            assert_eq!(buf[..], expected[..]);
+
+            cipher.init(&key, Operation::Decrypt);
+            cipher.apply_keystream_secret(&mut buf);
+            assert_eq!(buf[..], plaintext[..]);
+        }
+        // Fixed-size buffers work too, and the counter carries over between calls
+        let mut whole = Zeroizing::new([0u8; 128]);
+        let mut cipher = ChaCha20::new(&nonce, 0);
+        cipher.init(&key, Operation::Encrypt);
+        cipher.apply_keystream_secret(&mut whole);
+        let (mut first, mut second) = (Zeroizing::new([0u8; 64]), Zeroizing::new([0u8; 64]));
+        cipher.init(&key, Operation::Encrypt);
+        cipher.apply_keystream_secret(&mut first);
+        cipher.apply_keystream_secret(&mut second);
+        assert_eq!(whole[..64], first[..]);
+        assert_eq!(whole[64..], second[..]);
+    }
+
+    #[test]
+    pub fn hchacha20_xchacha_draft() {
+        // draft-irtf-cfrg-xchacha section 2.2.1
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let nonce = [
+            0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x4a, 0x00, 0x00, 0x00, 0x00, 0x31, 0x41,
+            0x59, 0x27,
+        ];
+        assert_eq!(
+            *hchacha20(&key, &nonce),
+            [
+                0x82, 0x41, 0x3b, 0x42, 0x27, 0xb2, 0x7b, 0xfe, 0xd3, 0x0e, 0x42, 0x50, 0x8a, 0x87,
+                0x7d, 0x73, 0xa0, 0xf9, 0xe4, 0xd5, 0x8a, 0x74, 0xa8, 0x53, 0xc1, 0x2e, 0xc4, 0x13,
+                0x26, 0xd3, 0xec, 0xdc
+            ]
+        );
+    }
+}
@@ -0,0 +1,508 @@
+use core::convert::TryInto;
+
+use alloc::vec::Vec;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{
+    aead::{Aead, AeadEncryptor},
+    chacha20::{chacha20_block, hchacha20, load_words, ChaCha20},
+    Operation, SymmetricCipher,
+};
+use crate::{
+    digest::Digest,
+    error::{Error, ErrorKind, Result},
+    mac::poly1305::Poly1305,
+};
+
+// Counter 0 is the Poly1305 key, so RFC 8439 leaves 2^32-1 blocks of 64 bytes for the plaintext
+const MAX_PLAINTEXT: u64 = (u32::MAX as u64) * 64;
+
+///
+/// The ChaCha20-Poly1305 AEAD of RFC 8439, with a 96-bit nonce and a 128-bit tag.
+///
+/// The Poly1305 key is taken from the first ChaCha20 block (counter 0), and the message is encrypted starting from counter 1.
+/// Since nonces must never repeat under one key, random nonces should only be used for a limited number of messages.
+pub struct ChaCha20Poly1305 {
+    key: Zeroizing<[u8; 32]>,
+}
+
+impl ChaCha20Poly1305 {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            key: Zeroizing::new(*key),
+        }
+    }
+}
+
+impl ChaCha20Poly1305 {
+    ///
+    /// Verifies the tag of `ciphertext` and only then decrypts it into `out`, which is zeroed if verification fails
+    fn open_into(
+        &self,
+        nonce: &[u8; 12],
+        aad: &[u8],
+        ciphertext: &[u8],
+        out: &mut [u8],
+    ) -> Result<()> {
+        let (ct, tag) = ciphertext.split_at(ciphertext.len() - Self::TAG_SIZE);
+        let expected = poly1305_tag(&self.key, nonce, aad, ct);
+        if !crate::cmp::eq(&expected, tag) {
+            out.zeroize();
+            return Err(ErrorKind::AuthenticationFailed.into());
+        }
+        out.copy_from_slice(ct);
+        chacha20_xor(&self.key, nonce, out);
+        Ok(())
+    }
+}
+
+impl Zeroize for ChaCha20Poly1305 {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+///
+/// Applies the ChaCha20 keystream for `key` and `nonce`, starting at counter 1, to `data` in place
+pub(crate) fn chacha20_xor(key: &[u8; 32], nonce: &[u8; 12], data: &mut [u8]) {
+    let mut cipher = ChaCha20::new(nonce, 1);
+    cipher.init(key, Operation::Encrypt);
+    let mut block = Zeroizing::new([0u8; 64]);
+    for chunk in data.chunks_mut(64) {
+        block[..chunk.len()].copy_from_slice(chunk);
+        cipher.do_final(&block[..chunk.len()], chunk);
+    }
+    cipher.zeroize();
+}
+
+fn load_key_nonce(key: &[u8; 32], nonce: &[u8; 12]) -> (Zeroizing<[u32; 8]>, [u32; 3]) {
+    (Zeroizing::new(load_words(key)), load_words(nonce))
+}
+
+///
+/// Derives the Poly1305 one-time key from the first 32 bytes of ChaCha20 block 0
+fn one_time_key(words: &[u32; 8], n: &[u32; 3]) -> Zeroizing<[u8; 32]> {
+    let mut block0 = Zeroizing::new([0u8; 64]);
+    chacha20_block(words, 0, n, &mut block0);
+    let mut otk = Zeroizing::new([0u8; 32]);
+    otk.copy_from_slice(&block0[..32]);
+    otk
+}
+
+///
+/// Computes the RFC 8439 tag over `aad` and `ct` under the one-time key derived from `key` and `nonce`
+pub(crate) fn poly1305_tag(key: &[u8; 32], nonce: &[u8; 12], aad: &[u8], ct: &[u8]) -> [u8; 16] {
+    let (words, n) = load_key_nonce(key, nonce);
+    let mut mac = Poly1305::new(&one_time_key(&words, &n));
+    // Each of aad and ct is zero-padded to a whole number of 16-byte blocks, so padding the final partial block is the same as feeding a full one
+    let mut pad = [0u8; 16];
+    for part in [aad, ct] {
+        let mut chunks = part.chunks_exact(16);
+        for c in &mut chunks {
+            mac.update(c);
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            pad.fill(0);
+            pad[..rest.len()].copy_from_slice(rest);
+            mac.update(&pad);
+        }
+    }
+    pad[..8].copy_from_slice(&(aad.len() as u64).to_le_bytes());
+    pad[8..].copy_from_slice(&(ct.len() as u64).to_le_bytes());
+    let mut tag = [0u8; 16];
+    mac.do_final(&pad, &mut tag);
+    tag
+}
+
+///
+/// Panics in `seal` if the plaintext is longer than the 2^38-64 bytes RFC 8439 allows.
+impl Aead for ChaCha20Poly1305 {
+    const KEY_SIZE: usize = 32;
+    const NONCE_SIZE: usize = 12;
+    const TAG_SIZE: usize = 16;
+
+    fn seal(&mut self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let nonce: &[u8; 12] = nonce.try_into().expect("nonce must be 12 bytes");
+        assert!(
+            plaintext.len() as u64 <= MAX_PLAINTEXT,
+            "input too long for ChaCha20-Poly1305"
+        );
+        let mut out = Vec::with_capacity(plaintext.len() + Self::TAG_SIZE);
+        out.extend_from_slice(plaintext);
+        chacha20_xor(&self.key, nonce, &mut out);
+        let tag = poly1305_tag(&self.key, nonce, aad, &out);
+        out.extend_from_slice(&tag);
+        out
+    }
+
+    fn open(&mut self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let nonce: &[u8; 12] = nonce.try_into().expect("nonce must be 12 bytes");
+        if ciphertext.len() < Self::TAG_SIZE
+            || (ciphertext.len() - Self::TAG_SIZE) as u64 > MAX_PLAINTEXT
+        {
+            return Err(ErrorKind::AuthenticationFailed.into());
+        }
+        let mut buf = Zeroizing::new(alloc::vec![0u8; ciphertext.len() - Self::TAG_SIZE]);
+        self.open_into(nonce, aad, ciphertext, &mut buf)?;
+        Ok(core::mem::take(&mut *buf))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EncryptorState {
+    Aad,
+    Text,
+    Finished,
+}
+
+///
+/// Incremental ChaCha20-Poly1305 encryption of a single message, producing the same output as [`ChaCha20Poly1305`].
+///
+/// See [`AeadEncryptor`] for the order in which methods must be called.
+pub struct ChaCha20Poly1305Encryptor {
+    key: Zeroizing<[u32; 8]>,
+    nonce: [u32; 3],
+    counter: u32,
+    keystream: Zeroizing<[u8; 64]>,
+    keystream_pos: usize,
+    mac: Poly1305,
+    mac_buf: Zeroizing<[u8; 16]>,
+    mac_len: usize,
+    aad_len: u64,
+    ct_len: u64,
+    state: EncryptorState,
+}
+
+impl ChaCha20Poly1305Encryptor {
+    pub fn new(key: &[u8; 32], nonce: &[u8; 12]) -> Self {
+        let (key, nonce) = load_key_nonce(key, nonce);
+        let mac = Poly1305::new(&one_time_key(&key, &nonce));
+        Self {
+            key,
+            nonce,
+            counter: 1,
+            keystream: Zeroizing::new([0; 64]),
+            keystream_pos: 64,
+            mac,
+            mac_buf: Zeroizing::new([0; 16]),
+            mac_len: 0,
+            aad_len: 0,
+            ct_len: 0,
+            state: EncryptorState::Aad,
+        }
+    }
+
+    fn misuse(msg: &'static str) -> Error {
+        Error::new_with_message(ErrorKind::InvalidInput, msg)
+    }
+
+    fn mac_input(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let n = (16 - self.mac_len).min(data.len());
+            self.mac_buf[self.mac_len..][..n].copy_from_slice(&data[..n]);
+            self.mac_len += n;
+            data = &data[n..];
+            if self.mac_len == 16 {
+                self.mac.update(&*self.mac_buf);
+                self.mac_len = 0;
+            }
+        }
+    }
+
+    ///
+    /// Zero-pads the buffered MAC input to a whole block, ending the associated data or ciphertext
+    fn mac_pad(&mut self) {
+        if self.mac_len != 0 {
+            self.mac_buf[self.mac_len..].fill(0);
+            self.mac.update(&*self.mac_buf);
+            self.mac_len = 0;
+        }
+    }
+}
+
+impl Zeroize for ChaCha20Poly1305Encryptor {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+        self.keystream.zeroize();
+        self.mac.zeroize();
+        self.mac_buf.zeroize();
+        self.state = EncryptorState::Finished;
+    }
+}
+
+impl AeadEncryptor for ChaCha20Poly1305Encryptor {
+    const TAG_SIZE: usize = 16;
+
+    fn update_aad(&mut self, aad: &[u8]) -> Result<()> {
+        match self.state {
+            EncryptorState::Aad => {}
+            EncryptorState::Text => return Err(Self::misuse("associated data after plaintext")),
+            EncryptorState::Finished => return Err(Self::misuse("encryption already finished")),
+        }
+        self.aad_len += aad.len() as u64;
+        self.mac_input(aad);
+        Ok(())
+    }
+
+    fn encrypt_chunk(&mut self, plaintext: &[u8], out: &mut [u8]) -> Result<()> {
+        match self.state {
+            EncryptorState::Aad => {
+                self.mac_pad();
+                self.state = EncryptorState::Text;
+            }
+            EncryptorState::Text => {}
+            EncryptorState::Finished => return Err(Self::misuse("encryption already finished")),
+        }
+        if out.len() != plaintext.len() {
+            return Err(Self::misuse("output length does not match plaintext"));
+        }
+        if self.ct_len + plaintext.len() as u64 > MAX_PLAINTEXT {
+            self.zeroize();
+            return Err(Self::misuse("plaintext too long for ChaCha20-Poly1305"));
+        }
+        for (o, p) in out.iter_mut().zip(plaintext) {
+            if self.keystream_pos == 64 {
+                chacha20_block(&self.key, self.counter, &self.nonce, &mut self.keystream);
+                // The length check above stops before a wrapped counter is ever used
+                self.counter = self.counter.wrapping_add(1);
+                self.keystream_pos = 0;
+            }
+            *o = p ^ self.keystream[self.keystream_pos];
+            self.keystream_pos += 1;
+        }
+        self.ct_len += out.len() as u64;
+        self.mac_input(out);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<Vec<u8>> {
+        if self.state == EncryptorState::Finished {
+            return Err(Self::misuse("encryption already finished"));
+        }
+        self.mac_pad();
+        let mut lengths = [0u8; 16];
+        lengths[..8].copy_from_slice(&self.aad_len.to_le_bytes());
+        lengths[8..].copy_from_slice(&self.ct_len.to_le_bytes());
+        let mut tag = alloc::vec![0u8; 16];
+        self.mac.do_final(&lengths, &mut tag);
+        self.zeroize();
+        Ok(tag)
+    }
+}
+
+///
+/// The XChaCha20-Poly1305 AEAD, which extends [`ChaCha20Poly1305`] to a 192-bit nonce.
+///
+/// The first 16 bytes of the nonce and the key are passed through HChaCha20 to derive a subkey,
+///  which is then used for ChaCha20-Poly1305 with the remaining 8 nonce bytes (prefixed by 4 zero bytes).
+/// The nonce is long enough that it can be generated at random for each message without a practical risk of collision.
+pub struct XChaCha20Poly1305 {
+    key: Zeroizing<[u8; 32]>,
+}
+
+impl XChaCha20Poly1305 {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            key: Zeroizing::new(*key),
+        }
+    }
+
+    fn subkey(&self, nonce: &[u8]) -> (Zeroizing<[u8; 32]>, [u8; 12]) {
+        assert_eq!(nonce.len(), Self::NONCE_SIZE, "nonce must be 24 bytes");
+        let mut prefix = [0u8; 16];
+        prefix.copy_from_slice(&nonce[..16]);
+        let mut inner = [0u8; 12];
+        inner[4..].copy_from_slice(&nonce[16..]);
+        (hchacha20(&self.key, &prefix), inner)
+    }
+}
+
+impl Zeroize for XChaCha20Poly1305 {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+///
+/// Panics in `seal` if the plaintext is longer than [`ChaCha20Poly1305`] allows.
+impl Aead for XChaCha20Poly1305 {
+    const KEY_SIZE: usize = 32;
+    const NONCE_SIZE: usize = 24;
+    const TAG_SIZE: usize = 16;
+
+    fn seal(&mut self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let (subkey, nonce) = self.subkey(nonce);
+        ChaCha20Poly1305::new(&subkey).seal(&nonce, aad, plaintext)
+    }
+
+    fn open(&mut self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let (subkey, nonce) = self.subkey(nonce);
+        ChaCha20Poly1305::new(&subkey).open(&nonce, aad, ciphertext)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec;
+
+    use super::{ChaCha20Poly1305, ChaCha20Poly1305Encryptor, XChaCha20Poly1305, MAX_PLAINTEXT};
+    use crate::{
+        error::ErrorKind,
+        symm::aead::{Aead, AeadEncryptor},
+    };
+
+    const KEY: [u8; 32] = [
+        0x80, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x8a, 0x8b, 0x8c, 0x8d, 0x8e,
+        0x8f, 0x90, 0x91, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0x9b, 0x9c, 0x9d,
+        0x9e, 0x9f,
+    ];
+    const NONCE: [u8; 12] = [
+        0x07, 0x00, 0x00, 0x00, 0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47,
+    ];
+    const AAD: [u8; 12] = [
+        0x50, 0x51, 0x52, 0x53, 0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7,
+    ];
+    const PLAINTEXT: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+    const CIPHERTEXT: [u8; 130] = [
+        0xd3, 0x1a, 0x8d, 0x34, 0x64, 0x8e, 0x60, 0xdb, 0x7b, 0x86, 0xaf, 0xbc, 0x53, 0xef, 0x7e,
+        0xc2, 0xa4, 0xad, 0xed, 0x51, 0x29, 0x6e, 0x08, 0xfe, 0xa9, 0xe2, 0xb5, 0xa7, 0x36, 0xee,
+        0x62, 0xd6, 0x3d, 0xbe, 0xa4, 0x5e, 0x8c, 0xa9, 0x67, 0x12, 0x82, 0xfa, 0xfb, 0x69, 0xda,
+        0x92, 0x72, 0x8b, 0x1a, 0x71, 0xde, 0x0a, 0x9e, 0x06, 0x0b, 0x29, 0x05, 0xd6, 0xa5, 0xb6,
+        0x7e, 0xcd, 0x3b, 0x36, 0x92, 0xdd, 0xbd, 0x7f, 0x2d, 0x77, 0x8b, 0x8c, 0x98, 0x03, 0xae,
+        0xe3, 0x28, 0x09, 0x1b, 0x58, 0xfa, 0xb3, 0x24, 0xe4, 0xfa, 0xd6, 0x75, 0x94, 0x55, 0x85,
+        0x80, 0x8b, 0x48, 0x31, 0xd7, 0xbc, 0x3f, 0xf4, 0xde, 0xf0, 0x8e, 0x4b, 0x7a, 0x9d, 0xe5,
+        0x76, 0xd2, 0x65, 0x86, 0xce, 0xc6, 0x4b, 0x61, 0x16, // tag
+        0x1a, 0xe1, 0x0b, 0x59, 0x4f, 0x09, 0xe2, 0x6a, 0x7e, 0x90, 0x2e, 0xcb, 0xd0, 0x60, 0x06,
+        0x91,
+    ];
+
+    #[test]
+    pub fn chacha20poly1305_rfc8439_seal() {
+        // RFC 8439 section 2.8.2
+        let mut aead = ChaCha20Poly1305::new(&KEY);
+        assert_eq!(aead.seal(&NONCE, &AAD, PLAINTEXT), CIPHERTEXT);
+    }
+
+    #[test]
+    pub fn chacha20poly1305_rfc8439_open() {
+        let mut aead = ChaCha20Poly1305::new(&KEY);
+        assert_eq!(aead.open(&NONCE, &AAD, &CIPHERTEXT).unwrap(), PLAINTEXT);
+        let mut tampered = CIPHERTEXT;
+        tampered[129] ^= 1;
+        assert_eq!(
+            aead.open(&NONCE, &AAD, &tampered).unwrap_err().kind(),
+            ErrorKind::AuthenticationFailed
+        );
+    }
+
+    #[test]
+    pub fn chacha20poly1305_failed_open_clears_plaintext() {
+        let aead = ChaCha20Poly1305::new(&KEY);
+        let mut tampered = CIPHERTEXT;
+        tampered[3] ^= 1;
+        let mut scratch = [0xffu8; 114];
+        assert_eq!(
+            aead.open_into(&NONCE, &AAD, &tampered, &mut scratch)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::AuthenticationFailed
+        );
+        assert_eq!(scratch, [0u8; 114]);
+    }
+
+    #[test]
+    pub fn chacha20poly1305_round_trip() {
+        crate::symm::aead::test::round_trip(ChaCha20Poly1305::new(&KEY));
+    }
+
+    #[test]
+    pub fn chacha20poly1305_encryptor_chunked() {
+        for (aad_split, pt_split) in [(0, 0), (5, 1), (12, 63), (3, 64), (7, 100), (12, 114)] {
+            let mut enc = ChaCha20Poly1305Encryptor::new(&KEY, &NONCE);
+            enc.update_aad(&AAD[..aad_split]).unwrap();
+            enc.update_aad(&AAD[aad_split..]).unwrap();
+            let mut out = vec![0u8; PLAINTEXT.len()];
+            let (head, tail) = out.split_at_mut(pt_split);
+            enc.encrypt_chunk(&PLAINTEXT[..pt_split], head).unwrap();
+            enc.encrypt_chunk(&PLAINTEXT[pt_split..], tail).unwrap();
+            out.extend_from_slice(&enc.finish().unwrap());
+            assert_eq!(out, CIPHERTEXT);
+        }
+    }
+
+    #[test]
+    pub fn chacha20poly1305_encryptor_empty_matches_seal() {
+        let mut enc = ChaCha20Poly1305Encryptor::new(&KEY, &NONCE);
+        enc.update_aad(&AAD).unwrap();
+        let tag = enc.finish().unwrap();
+        assert_eq!(tag, ChaCha20Poly1305::new(&KEY).seal(&NONCE, &AAD, &[]));
+    }
+
+    #[test]
+    pub fn chacha20poly1305_encryptor_misuse() {
+        let mut enc = ChaCha20Poly1305Encryptor::new(&KEY, &NONCE);
+        let mut out = [0u8; 4];
+        enc.encrypt_chunk(b"abcd", &mut out).unwrap();
+        assert_eq!(
+            enc.update_aad(&AAD).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            enc.encrypt_chunk(b"abc", &mut out).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        enc.finish().unwrap();
+        assert_eq!(enc.finish().unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            enc.encrypt_chunk(b"abcd", &mut out).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    pub fn chacha20poly1305_encryptor_length_limit() {
+        let mut enc = ChaCha20Poly1305Encryptor::new(&KEY, &NONCE);
+        // Pretend most of the message has already been encrypted
+        enc.ct_len = MAX_PLAINTEXT - 4;
+        let mut out = [0u8; 5];
+        enc.encrypt_chunk(b"abcd", &mut out[..4]).unwrap();
+        assert_eq!(
+            enc.encrypt_chunk(b"e", &mut out[..1]).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        // The encryptor is finished after exceeding the limit
+        assert_eq!(enc.finish().unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            enc.encrypt_chunk(b"", &mut out[..0]).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    pub fn xchacha20poly1305_draft() {
+        // draft-irtf-cfrg-xchacha appendix A.3.1
+        let nonce: [u8; 24] = core::array::from_fn(|i| 0x40 + i as u8);
+        let expected = [
+            0xbd, 0x6d, 0x17, 0x9d, 0x3e, 0x83, 0xd4, 0x3b, 0x95, 0x76, 0x57, 0x94, 0x93, 0xc0,
+            0xe9, 0x39, 0x57, 0x2a, 0x17, 0x00, 0x25, 0x2b, 0xfa, 0xcc, 0xbe, 0xd2, 0x90, 0x2c,
+            0x21, 0x39, 0x6c, 0xbb, 0x73, 0x1c, 0x7f, 0x1b, 0x0b, 0x4a, 0xa6, 0x44, 0x0b, 0xf3,
+            0xa8, 0x2f, 0x4e, 0xda, 0x7e, 0x39, 0xae, 0x64, 0xc6, 0x70, 0x8c, 0x54, 0xc2, 0x16,
+            0xcb, 0x96, 0xb7, 0x2e, 0x12, 0x13, 0xb4, 0x52, 0x2f, 0x8c, 0x9b, 0xa4, 0x0d, 0xb5,
+            0xd9, 0x45, 0xb1, 0x1b, 0x69, 0xb9, 0x82, 0xc1, 0xbb, 0x9e, 0x3f, 0x3f, 0xac, 0x2b,
+            0xc3, 0x69, 0x48, 0x8f, 0x76, 0xb2, 0x38, 0x35, 0x65, 0xd3, 0xff, 0xf9, 0x21, 0xf9,
+            0x66, 0x4c, 0x97, 0x63, 0x7d, 0xa9, 0x76, 0x88, 0x12, 0xf6, 0x15, 0xc6, 0x8b, 0x13,
+            0xb5, 0x2e, // tag
+            0xc0, 0x87, 0x59, 0x24, 0xc1, 0xc7, 0x98, 0x79, 0x47, 0xde, 0xaf, 0xd8, 0x78, 0x0a,
+            0xcf, 0x49,
+        ];
+        let mut aead = XChaCha20Poly1305::new(&KEY);
+        assert_eq!(aead.seal(&nonce, &AAD, PLAINTEXT), expected);
+        assert_eq!(aead.open(&nonce, &AAD, &expected).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    pub fn xchacha20poly1305_round_trip() {
+        crate::symm::aead::test::round_trip(XChaCha20Poly1305::new(&KEY));
+    }
+}
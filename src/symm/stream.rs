@@ -0,0 +1,170 @@
+//!
+//! Encryption of arbitrarily long streams with keystream ciphers, such as [`super::chacha20::ChaCha20`].
+
+use std::io::{self, ErrorKind, Read, Write};
+
+use alloc::vec;
+use zeroize::Zeroizing;
+
+use super::{Operation, SymmetricCipher};
+
+fn read_block<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(len)
+}
+
+///
+/// Encrypts everything read from `reader` with `cipher` under `key`, writing the result to `writer`.
+///
+/// Input is processed one `C::BLOCK_SIZE` block at a time, and only the final block is passed to [`SymmetricCipher::do_final`],
+///  so the whole stream is never held in memory. All intermediate buffers are zeroed when they are dropped.
+///
+/// `C` should be a keystream cipher (for example CTR, OFB, or ChaCha20), which accepts a partial final block and for which decryption is the same operation as encryption.
+pub fn encrypt_stream<C: SymmetricCipher, R: Read, W: Write>(
+    mut cipher: C,
+    key: &[u8],
+    mut reader: R,
+    mut writer: W,
+) -> io::Result<()> {
+    cipher.init(key, Operation::Encrypt);
+    let mut cur = Zeroizing::new(vec![0u8; C::BLOCK_SIZE]);
+    let mut next = Zeroizing::new(vec![0u8; C::BLOCK_SIZE]);
+    let mut out = Zeroizing::new(vec![0u8; C::BLOCK_SIZE]);
+    let mut len = read_block(&mut reader, &mut cur)?;
+    while len == C::BLOCK_SIZE {
+        let next_len = read_block(&mut reader, &mut next)?;
+        if next_len == 0 {
+            break;
+        }
+        cipher.update(&cur, &mut out);
+        writer.write_all(&out)?;
+        core::mem::swap(&mut cur, &mut next);
+        len = next_len;
+    }
+    let last = cipher.do_final(&cur[..len], &mut out);
+    writer.write_all(&last)?;
+    drop(last);
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{Cursor, Read, Write},
+        sync::mpsc::{channel, Receiver, Sender},
+    };
+
+    use alloc::vec::Vec;
+
+    use super::encrypt_stream;
+    use crate::symm::chacha20::ChaCha20;
+
+    ///
+    /// The writing end of an in-memory pipe, which sends each write to the reading end
+    struct PipeWriter(Sender<Vec<u8>>);
+
+    impl Write for PipeWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .send(buf.to_vec())
+                .map_err(|_| std::io::ErrorKind::BrokenPipe.into())
+                .map(|()| buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    ///
+    /// The reading end of an in-memory pipe, which reports end-of-file once the writer is dropped
+    struct PipeReader(Receiver<Vec<u8>>, Cursor<Vec<u8>>);
+
+    impl Read for PipeReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            loop {
+                let n = self.1.read(buf)?;
+                if n != 0 || buf.is_empty() {
+                    return Ok(n);
+                }
+                match self.0.recv() {
+                    Ok(chunk) => self.1 = Cursor::new(chunk),
+                    Err(_) => return Ok(0),
+                }
+            }
+        }
+    }
+
+    fn pipe() -> (PipeReader, PipeWriter) {
+        let (tx, rx) = channel();
+        (PipeReader(rx, Cursor::new(Vec::new())), PipeWriter(tx))
+    }
+
+    #[test]
+    pub fn encrypt_stream_pipe_round_trip() {
+        let key = [0x42u8; 32];
+        let nonce = [7u8; 12];
+        let plaintext: Vec<u8> = (0..1000u32).map(|i| (i * 31) as u8).collect();
+
+        let (mut pipe_reader, pipe_writer) = pipe();
+        let input = plaintext.clone();
+        let writer_thread = std::thread::spawn(move || {
+            encrypt_stream(
+                ChaCha20::new(&nonce, 0),
+                &key,
+                Cursor::new(input),
+                pipe_writer,
+            )
+        });
+        let mut ciphertext = Vec::new();
+        pipe_reader.read_to_end(&mut ciphertext).unwrap();
+        writer_thread.join().unwrap().unwrap();
+        assert_eq!(ciphertext.len(), plaintext.len());
+        assert_ne!(ciphertext, plaintext);
+
+        let mut decrypted = Vec::new();
+        encrypt_stream(
+            ChaCha20::new(&nonce, 0),
+            &key,
+            Cursor::new(ciphertext),
+            &mut decrypted,
+        )
+        .unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    pub fn encrypt_stream_block_multiple() {
+        let key = [1u8; 32];
+        let nonce = [2u8; 12];
+        for len in [0, 64, 128] {
+            let plaintext = alloc::vec![0x55u8; len];
+            let mut ciphertext = Vec::new();
+            encrypt_stream(
+                ChaCha20::new(&nonce, 0),
+                &key,
+                &plaintext[..],
+                &mut ciphertext,
+            )
+            .unwrap();
+            assert_eq!(ciphertext.len(), len);
+            let mut decrypted = Vec::new();
+            encrypt_stream(
+                ChaCha20::new(&nonce, 0),
+                &key,
+                &ciphertext[..],
+                &mut decrypted,
+            )
+            .unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//!
+//! Statistical timing tests of the constant-time primitives, in the style of dudect.
+//!
+//! Each test times an operation on two classes of input (one fixed, one random), interleaved at random,
+//!  and applies Welch's t-test to the two distributions of timings. A large |t| means the timing depends on the input class.
+//! Timing is noisy, so these only run with the `timing-tests` feature, and are best run on an otherwise idle machine with
+//!  `cargo test --release --features timing-tests timing -- --test-threads=1`.
+
+use std::{hint::black_box, time::Instant, vec::Vec};
+
+use crate::secret::SecretUint;
+
+// dudect treats |t| above 10 as a definite leak; lower values are too easily reached by scheduling noise alone
+const T_THRESHOLD: f64 = 10.0;
+const SAMPLES: usize = 20000;
+const BATCH: usize = 16;
+// Samples taken and thrown away first, while caches and the branch predictor warm up
+const WARMUP: usize = 2000;
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn fill(&mut self, out: &mut [u8]) {
+        for b in out {
+            *b = self.next() as u8;
+        }
+    }
+}
+
+#[derive(Default)]
+struct Welford {
+    n: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Welford {
+    fn push(&mut self, x: f64) {
+        self.n += 1.0;
+        let delta = x - self.mean;
+        self.mean += delta / self.n;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn variance(&self) -> f64 {
+        self.m2 / (self.n - 1.0)
+    }
+}
+
+///
+/// Times `op` on inputs prepared by `prepare(class, rng, input)`, and returns Welch's t statistic between the two classes
+fn welch_t<I, P: FnMut(usize, &mut XorShift, &mut I), O: FnMut(&I)>(
+    mut input: I,
+    mut prepare: P,
+    mut op: O,
+) -> f64 {
+    let mut rng = XorShift(0x2545f4914f6cdd1d);
+    let mut samples: Vec<(usize, f64)> = Vec::with_capacity(WARMUP + SAMPLES);
+    for _ in 0..WARMUP + SAMPLES {
+        let class = (rng.next() & 1) as usize;
+        prepare(class, &mut rng, &mut input);
+        let start = Instant::now();
+        for _ in 0..BATCH {
+            op(black_box(&input));
+        }
+        samples.push((class, start.elapsed().as_nanos() as f64));
+    }
+    samples.drain(..WARMUP);
+    // Discard the slowest 10%, which are dominated by interrupts and preemption
+    let mut sorted: Vec<f64> = samples.iter().map(|s| s.1).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let cutoff = sorted[sorted.len() * 9 / 10];
+    let mut stats = [Welford::default(), Welford::default()];
+    for (class, t) in samples {
+        if t <= cutoff {
+            stats[class].push(t);
+        }
+    }
+    let [a, b] = stats;
+    (a.mean - b.mean) / (a.variance() / a.n + b.variance() / b.n).sqrt()
+}
+
+#[test]
+pub fn timing_cmp_eq() {
+    let reference = [0x42u8; 256];
+    let t = welch_t(
+        [0u8; 256],
+        |class, rng, input| {
+            if class == 0 {
+                *input = reference;
+            } else {
+                rng.fill(input);
+            }
+        },
+        |input| {
+            black_box(crate::cmp::eq(black_box(&reference), input));
+        },
+    );
+    assert!(t.abs() < T_THRESHOLD, "cmp::eq leaks timing: t = {}", t);
+}
+
+#[test]
+pub fn timing_ct_table_lookup() {
+    let mut table = [0u32; 256];
+    for (i, v) in table.iter_mut().enumerate() {
+        *v = (i as u32).wrapping_mul(0x9e3779b9);
+    }
+    let t = welch_t(
+        0usize,
+        |class, rng, input| {
+            *input = if class == 0 {
+                0
+            } else {
+                rng.next() as usize & 0xff
+            }
+        },
+        |&index| {
+            black_box(crate::asm::ct_table_lookup(index, black_box(&table)));
+        },
+    );
+    assert!(
+        t.abs() < T_THRESHOLD,
+        "ct_table_lookup leaks timing: t = {}",
+        t
+    );
+}
+
+#[test]
+pub fn timing_conditional_select() {
+    let a = SecretUint::<32>::from_be_bytes([0x11; 32]);
+    let b = SecretUint::<32>::from_be_bytes([0xee; 32]);
+    let t = welch_t(
+        false,
+        |class, rng, input| *input = class == 0 || rng.next() & 1 == 0,
+        |&choice| {
+            black_box(SecretUint::conditional_select(&a, &b, black_box(choice)));
+        },
+    );
+    assert!(
+        t.abs() < T_THRESHOLD,
+        "SecretUint::conditional_select leaks timing: t = {}",
+        t
+    );
+}
@@ -0,0 +1,140 @@
+use crate::error::{ErrorKind, Result};
+
+///
+/// The number of times [`retry_interrupted`] calls its operation before giving up
+pub const MAX_INTERRUPTED_ATTEMPTS: usize = 16;
+
+///
+/// Calls `f` until it returns something other than an error of kind [`ErrorKind::Interrupted`], and returns that result.
+///
+/// This follows the convention of `std::io::Read`, where an interrupted operation should simply be retried.
+/// After [`MAX_INTERRUPTED_ATTEMPTS`] interrupted attempts, the last error is returned, so a persistently interrupted operation cannot loop forever.
+pub fn retry_interrupted<T, F: FnMut() -> Result<T>>(mut f: F) -> Result<T> {
+    let mut attempts = 1;
+    loop {
+        match f() {
+            Err(e) if e.kind() == ErrorKind::Interrupted && attempts < MAX_INTERRUPTED_ATTEMPTS => {
+                attempts += 1
+            }
+            r => return r,
+        }
+    }
+}
+
+///
+/// Detects at runtime whether the CPU supports a target feature, such as `"avx2"` or `"neon"`, on any architecture.
+///
+/// With the `std` feature, this expands to `is_x86_feature_detected!` on x86 and x86_64, and `is_aarch64_feature_detected!` on aarch64.
+/// Otherwise (including on other architectures) it falls back to `cfg!(target_feature = ...)`, which is only true if the feature is enabled at compile time.
+///
+/// ## Examples
+///
+/// ```
+/// if lc_crypto::cpu_feature_detected!("avx2") {
+///     // Use the AVX2 implementation
+/// }
+/// ```
+#[macro_export]
+macro_rules! cpu_feature_detected {
+    ($feature:tt) => {
+        $crate::__cpu_feature_detected!($feature)
+    };
+}
+
+// The implementations are chosen by the cfg of this crate, which a cfg inside the expansion would evaluate in the calling crate instead
+#[cfg(all(
+    any(test, feature = "std"),
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cpu_feature_detected {
+    ($feature:tt) => {
+        $crate::__std::arch::is_x86_feature_detected!($feature)
+    };
+}
+
+#[cfg(all(any(test, feature = "std"), target_arch = "aarch64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cpu_feature_detected {
+    ($feature:tt) => {
+        $crate::__std::arch::is_aarch64_feature_detected!($feature)
+    };
+}
+
+#[cfg(not(all(
+    any(test, feature = "std"),
+    any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cpu_feature_detected {
+    ($feature:tt) => {
+        cfg!(target_feature = $feature)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::{retry_interrupted, MAX_INTERRUPTED_ATTEMPTS};
+    use crate::error::{Error, ErrorKind};
+
+    #[test]
+    pub fn retry_interrupted_twice() {
+        let mut calls = 0;
+        let r = retry_interrupted(|| {
+            calls += 1;
+            if calls <= 2 {
+                Err(Error::from(ErrorKind::Interrupted))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(r.unwrap(), 3);
+    }
+
+    #[test]
+    pub fn retry_interrupted_other_error() {
+        let mut calls = 0;
+        let r: crate::error::Result<()> = retry_interrupted(|| {
+            calls += 1;
+            Err(ErrorKind::InvalidInput.into())
+        });
+        assert_eq!(r.unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    pub fn retry_interrupted_bounded() {
+        let mut calls = 0;
+        let r: crate::error::Result<()> = retry_interrupted(|| {
+            calls += 1;
+            Err(ErrorKind::Interrupted.into())
+        });
+        assert_eq!(r.unwrap_err().kind(), ErrorKind::Interrupted);
+        assert_eq!(calls, MAX_INTERRUPTED_ATTEMPTS);
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    pub fn cpu_feature_detected_x86_64() {
+        // SSE2 is part of the x86_64 baseline
+        assert!(crate::cpu_feature_detected!("sse2"));
+        assert_eq!(
+            crate::cpu_feature_detected!("avx2"),
+            std::is_x86_feature_detected!("avx2")
+        );
+    }
+
+    #[test]
+    #[cfg(target_arch = "aarch64")]
+    pub fn cpu_feature_detected_aarch64() {
+        // NEON is part of the aarch64 baseline
+        assert!(crate::cpu_feature_detected!("neon"));
+        assert_eq!(
+            crate::cpu_feature_detected!("aes"),
+            std::arch::is_aarch64_feature_detected!("aes")
+        );
+    }
+}
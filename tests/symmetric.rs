@@ -0,0 +1,119 @@
+//!
+//! Known-answer tests for block cipher modes, read from NIST CAVP-style response (`.rsp`) files in `tests/data`.
+
+use lc_crypto::symm::{aes::Aes, decrypt, encrypt, SymmetricCipher, CBC};
+
+#[derive(Default)]
+struct Vector {
+    count: usize,
+    encrypt: bool,
+    key: Vec<u8>,
+    iv: Vec<u8>,
+    plaintext: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+///
+/// Parses a response file into its vectors.
+///
+/// Each vector is a block of `NAME = value` lines beginning with `COUNT`, and applies to the direction given by the most recent `[ENCRYPT]` or `[DECRYPT]` header.
+/// Comments (`#`) and unknown names are ignored. `PT`/`CT` are accepted as short forms of `PLAINTEXT`/`CIPHERTEXT`.
+fn parse_rsp(text: &str) -> Vec<Vector> {
+    let mut vectors: Vec<Vector> = Vec::new();
+    let mut encrypt = true;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line {
+            "[ENCRYPT]" => encrypt = true,
+            "[DECRYPT]" => encrypt = false,
+            _ if line.starts_with('[') => {}
+            _ => {
+                let (name, value) = line.split_once('=').expect("malformed line");
+                let (name, value) = (name.trim(), value.trim());
+                if name == "COUNT" {
+                    vectors.push(Vector {
+                        count: value.parse().unwrap(),
+                        encrypt,
+                        ..Vector::default()
+                    });
+                    continue;
+                }
+                let v = vectors.last_mut().expect("value before COUNT");
+                match name {
+                    "KEY" => v.key = unhex(value),
+                    "IV" => v.iv = unhex(value),
+                    "PLAINTEXT" | "PT" => v.plaintext = unhex(value),
+                    "CIPHERTEXT" | "CT" => v.ciphertext = unhex(value),
+                    _ => {}
+                }
+            }
+        }
+    }
+    vectors
+}
+
+fn run_vector<C: SymmetricCipher>(v: &Vector, mk: impl Fn() -> C) {
+    if v.encrypt {
+        assert_eq!(
+            encrypt(mk(), &v.key, &v.plaintext),
+            v.ciphertext,
+            "encrypt COUNT = {}",
+            v.count
+        );
+    } else {
+        assert_eq!(
+            decrypt(mk(), &v.key, &v.ciphertext),
+            v.plaintext,
+            "decrypt COUNT = {}",
+            v.count
+        );
+    }
+    // Each vector must also survive a round trip in the other direction
+    let ct = encrypt(mk(), &v.key, &v.plaintext);
+    assert_eq!(decrypt(mk(), &v.key, &ct), v.plaintext);
+}
+
+///
+/// Generates a test which runs every vector in `tests/data/$file`, constructing the cipher with `$mk` for the key size `N` of each vector
+macro_rules! mk_symm_test {
+    ($name:ident, $file:literal, |$iv:ident| $mk:expr) => {
+        #[test]
+        fn $name() {
+            let vectors = parse_rsp(include_str!(concat!("data/", $file)));
+            assert!(!vectors.is_empty());
+            for v in &vectors {
+                let $iv = &v.iv;
+                match v.key.len() {
+                    16 => run_vector(v, || {
+                        type A = Aes<128>;
+                        $mk
+                    }),
+                    24 => run_vector(v, || {
+                        type A = Aes<192>;
+                        $mk
+                    }),
+                    32 => run_vector(v, || {
+                        type A = Aes<256>;
+                        $mk
+                    }),
+                    n => panic!("unsupported key length {}", n),
+                }
+            }
+        }
+    };
+}
+
+mk_symm_test!(aes_ecb, "aes_ecb.rsp", |_iv| A::const_new());
+mk_symm_test!(aes_cbc, "aes_cbc.rsp", |iv| CBC::new(
+    A::const_new(),
+    iv[..].into()
+));